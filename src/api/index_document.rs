@@ -1,14 +1,206 @@
+use rocket::serde::json::serde_json::{self, Map, Value};
 use rocket::serde::Deserialize;
+use rucene::rucene_internal::document::StoredFields;
+use rucene::rucene_internal::schema::{Schema, SOURCE_FIELD};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+/// A document to index: its id, and its source, e.g.
+/// `{ "id": "back-to-the-future", "title": "Back to the Future", "year": 1985, "cast": [...] }`.
+/// The id can be a string or a number, and is generated when it is left out. The source is stored
+/// as it is, and returned with hits. Of its fields, those in the schema are indexed: strings,
+/// numbers and booleans as text, and arrays of them as several values of the field.
 #[derive(Deserialize)]
 pub struct IndexDocument {
-    pub id: u32,
-    pub body: String,
+    #[serde(default)]
+    id: Option<DocumentId>,
+    #[serde(flatten)]
+    pub source: Map<String, Value>,
+}
+
+/// Ids are strings, but numbers are accepted as well.
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+enum DocumentId {
+    String(String),
+    Number(u64),
 }
 
 impl IndexDocument {
-    #[cfg(test)]
-    pub(crate) fn new(id: u32, body: String) -> Self {
-        IndexDocument { id, body }
+    /// Returns the id of the document, if it has one.
+    pub(crate) fn id(&self) -> Option<String> {
+        self.id.as_ref().map(|id| match id {
+            DocumentId::String(id) => id.clone(),
+            DocumentId::Number(id) => id.to_string(),
+        })
+    }
+
+    /// Returns the id of the document, or a new unique id if it has none.
+    pub(crate) fn id_or_generate(&self) -> String {
+        self.id().unwrap_or_else(generate_id)
+    }
+
+    /// Returns the text of each value of the fields of the source that are in the schema. Nested
+    /// objects and nulls have no text.
+    pub(crate) fn fields(&self, schema: &Schema) -> Vec<(&str, String)> {
+        let mut fields = vec![];
+
+        for (name, value) in &self.source {
+            if schema.field(name).is_none() {
+                continue;
+            }
+
+            let values = match value {
+                Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+
+            for value in values {
+                match value {
+                    Value::String(text) => fields.push((name.as_str(), text.clone())),
+                    Value::Number(_) | Value::Bool(_) => {
+                        fields.push((name.as_str(), value.to_string()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        fields
+    }
+
+    /// Returns the source as JSON, to be stored.
+    pub(crate) fn source_json(&self) -> String {
+        Value::from(self.source.clone()).to_string()
+    }
+
+    /// Adds the fields of the source of the indexed version of the document that this one does
+    /// not have, so that an update only changes the fields it gives.
+    pub(crate) fn merge(&mut self, stored_fields: &StoredFields) {
+        for (name, value) in source(stored_fields) {
+            self.source.entry(name).or_insert(value);
+        }
+    }
+}
+
+/// Returns the source of an indexed document: the JSON it was indexed with, or its stored fields
+/// if it was indexed without a source.
+pub(crate) fn source(stored_fields: &StoredFields) -> Map<String, Value> {
+    if let Some(source) = stored_fields
+        .get(SOURCE_FIELD)
+        .and_then(|source| serde_json::from_str(source).ok())
+    {
+        return source;
+    }
+
+    stored_fields
+        .iter()
+        .filter(|(name, _)| *name != SOURCE_FIELD)
+        .map(|(name, text)| (name.clone(), Value::String(text.clone())))
+        .collect()
+}
+
+/// Generates an id from the current time and a counter, so ids generated in the same nanosecond
+/// still differ.
+fn generate_id() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{:x}{:08x}", nanos, count)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::index_document::{generate_id, source, IndexDocument};
+    use rocket::serde::json::serde_json::{self, json, Value};
+    use rucene::rucene_internal::document::StoredFields;
+    use rucene::rucene_internal::schema::{Field, Schema, SOURCE_FIELD};
+
+    fn parse(json: &str) -> IndexDocument {
+        serde_json::from_str::<IndexDocument>(json).unwrap()
+    }
+
+    fn schema() -> Schema {
+        Schema::new()
+            .with_field(Field::new("title".to_string()))
+            .with_field(Field::new("year".to_string()))
+            .with_field(Field::new("cast".to_string()))
+    }
+
+    #[test]
+    fn can_parse_ids() {
+        assert_eq!(
+            parse(r#"{ "id": "https://example.com/", "title": "Example" }"#).id(),
+            Some("https://example.com/".to_string())
+        );
+        assert_eq!(parse(r#"{ "id": 7 }"#).id(), Some("7".to_string()));
+
+        let document = parse(r#"{ "title": "Example" }"#);
+
+        assert_eq!(document.id(), None);
+        assert_eq!(
+            document.fields(&schema()),
+            vec![("title", "Example".to_string())]
+        );
+    }
+
+    #[test]
+    fn indexes_fields_of_schema() {
+        let document = parse(
+            r#"{ "title": "Back to the Future", "year": 1985, "cast": ["Fox", "Lloyd"],
+                 "studio": "Universal", "rating": { "imdb": 8.5 } }"#,
+        );
+
+        assert_eq!(
+            document.fields(&schema()),
+            vec![
+                ("cast", "Fox".to_string()),
+                ("cast", "Lloyd".to_string()),
+                ("title", "Back to the Future".to_string()),
+                ("year", "1985".to_string()),
+            ]
+        );
+
+        // Everything else is only kept in the source.
+        let source: Value = serde_json::from_str(&document.source_json()).unwrap();
+
+        assert_eq!(source["studio"], "Universal");
+        assert_eq!(source["rating"], json!({ "imdb": 8.5 }));
+    }
+
+    #[test]
+    fn can_merge_source() {
+        let mut document = parse(r#"{ "id": 1, "title": "Future Cop" }"#);
+        let stored_fields = StoredFields::from([(
+            SOURCE_FIELD.to_string(),
+            r#"{"title":"Back to the Future","year":1985}"#.to_string(),
+        )]);
+
+        document.merge(&stored_fields);
+
+        assert_eq!(
+            Value::from(document.source),
+            json!({ "title": "Future Cop", "year": 1985 })
+        );
+    }
+
+    #[test]
+    fn source_falls_back_to_stored_fields() {
+        let stored_fields = StoredFields::from([("title".to_string(), "Future Cop".to_string())]);
+
+        assert_eq!(
+            Value::from(source(&stored_fields)),
+            json!({ "title": "Future Cop" })
+        );
+    }
+
+    #[test]
+    fn generated_ids_are_unique() {
+        assert_ne!(generate_id(), generate_id());
     }
 }
@@ -1,6 +1,4 @@
-pub(crate) mod analyser;
-mod character_filters;
 pub(crate) mod index_document;
 pub(crate) mod responses;
-mod token_filters;
-mod tokenizers;
+pub(crate) mod search_request;
+pub(crate) mod source_filter;
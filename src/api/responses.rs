@@ -1,7 +1,37 @@
+use crate::api::index_document::source;
+use crate::api::source_filter::SourceFilter;
+use rocket::serde::json::serde_json::{Map, Value};
 use rocket::serde::Serialize;
+use rucene::rucene_internal::document::DocumentResult;
 
 #[derive(Serialize)]
 pub(crate) struct IndexResponse {
     pub success: bool,
     pub error: String,
+    pub id: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SearchResponse {
+    pub success: bool,
+    pub error: String,
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SearchHit {
+    pub id: String,
+    pub score: f32,
+    #[serde(rename = "_source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<Map<String, Value>>,
+}
+
+impl SearchHit {
+    pub fn new(result: &DocumentResult, source_filter: &SourceFilter) -> Self {
+        SearchHit {
+            id: result.id.clone(),
+            score: result.score,
+            source: source_filter.filter(source(&result.stored_fields)),
+        }
+    }
 }
@@ -0,0 +1,254 @@
+use crate::api::source_filter::SourceFilter;
+use regex::Regex;
+use rocket::serde::de::Error as _;
+use rocket::serde::{Deserialize, Deserializer};
+use rucene::rucene_internal::query::{BooleanQuery, MatchQueryBuilder, Operator, Query};
+use rucene::rucene_internal::schema::{Field, Schema, DEFAULT_FIELD};
+use std::error::Error;
+use std::sync::OnceLock;
+
+/// JSON body of a search request. Each clause is free text that goes through the analysis of the
+/// default field; every resulting token becomes a clause of the boolean query.
+/// Quoted text becomes a phrase clause instead, and can be followed by `~` and a slop.
+/// `match` adds a required clause built from all of its tokens, combined with its `operator`,
+/// on its `field` (the default field if not given). `_source` filters the stored fields returned
+/// with each hit, see `SourceFilter`.
+/// # Example
+/// ```json
+/// { "must": "cat", "must_not": "dog", "should": "black \"black cat\"~2", "minimum_should_match": 0 }
+/// { "match": { "query": "Back to the Future", "operator": "and", "field": "title" }, "_source": ["title"] }
+/// ```
+#[derive(Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub must: String,
+    #[serde(default)]
+    pub must_not: String,
+    #[serde(default)]
+    pub should: String,
+    #[serde(default)]
+    pub minimum_should_match: usize,
+    #[serde(default, rename = "match")]
+    pub match_query: Option<MatchRequest>,
+    #[serde(default, rename = "_source")]
+    pub source: SourceFilter,
+}
+
+#[derive(Deserialize)]
+pub struct MatchRequest {
+    pub query: String,
+    #[serde(default, deserialize_with = "deserialize_operator")]
+    pub operator: Operator,
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
+/// Deserializes `or` or `and` into an `Operator`.
+fn deserialize_operator<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Operator, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(D::Error::custom)
+}
+
+impl SearchRequest {
+    #[cfg(test)]
+    pub(crate) fn new(must: String, must_not: String, should: String) -> Self {
+        SearchRequest {
+            must,
+            must_not,
+            should,
+            minimum_should_match: 0,
+            match_query: None,
+            source: SourceFilter::default(),
+        }
+    }
+
+    pub(crate) fn to_query(&self, schema: &Schema) -> Result<Query, Box<dyn Error>> {
+        let builder = MatchQueryBuilder::new(Self::indexed_field(schema, DEFAULT_FIELD)?);
+
+        let mut must = Self::to_clauses(&self.must, &builder);
+
+        if let Some(match_query) = &self.match_query {
+            let field = match_query.field.as_deref().unwrap_or(DEFAULT_FIELD);
+
+            must.push(
+                MatchQueryBuilder::new(Self::indexed_field(schema, field)?)
+                    .with_operator(match_query.operator)
+                    .build(&match_query.query),
+            );
+        }
+
+        Ok(BooleanQuery::new(
+            must,
+            Self::to_clauses(&self.must_not, &builder),
+            Self::to_clauses(&self.should, &builder),
+        )
+        .with_minimum_should_match(self.minimum_should_match)
+        .into())
+    }
+
+    fn indexed_field<'a>(schema: &'a Schema, name: &str) -> Result<&'a Field, Box<dyn Error>> {
+        match schema.field(name) {
+            Some(field) if field.indexed => Ok(field),
+            Some(_) => Err(format!("Field '{0}' is not indexed.", name).into()),
+            None => Err(format!("Unknown field '{0}'.", name).into()),
+        }
+    }
+
+    fn to_clauses(text: &str, builder: &MatchQueryBuilder) -> Vec<Query> {
+        static PHRASE: OnceLock<Regex> = OnceLock::new();
+        let re = PHRASE.get_or_init(|| Regex::new(r#""([^"]*)"(~(\d+))?"#).unwrap());
+
+        let mut phrase_clauses = vec![];
+        let mut remaining_text = String::new();
+        let mut last_end = 0;
+
+        for captures in re.captures_iter(text) {
+            let phrase = captures.get(0).unwrap();
+
+            remaining_text.push_str(&text[last_end..phrase.start()]);
+            remaining_text.push(' ');
+            last_end = phrase.end();
+
+            let slop = captures
+                .get(3)
+                .map(|slop| slop.as_str().parse().unwrap_or(u32::MAX))
+                .unwrap_or(0);
+
+            match builder.build_phrase(&captures[1], slop) {
+                Query::MatchNone => {}
+                phrase_clause => phrase_clauses.push(phrase_clause),
+            }
+        }
+
+        remaining_text.push_str(&text[last_end..]);
+
+        // A quote without a closing one starts no phrase, and is not part of the next term.
+        let remaining_text = remaining_text.replace('"', " ");
+
+        let mut clauses: Vec<Query> = builder
+            .analyse(&remaining_text)
+            .into_iter()
+            .map(|token| builder.term(token.value))
+            .collect();
+        clauses.extend(phrase_clauses);
+
+        clauses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::search_request::{MatchRequest, SearchRequest};
+    use rocket::serde::json::serde_json;
+    use rucene::rucene_internal::query::{BooleanQuery, Operator, PhraseQuery, Query, TermQuery};
+    use rucene::rucene_internal::schema::{Field, Schema};
+    use rucene::rucene_internal::token::Token;
+
+    fn schema() -> Schema {
+        Schema::default().with_field(Field::new("title".to_string()))
+    }
+
+    #[test]
+    fn can_build_query() {
+        let request = SearchRequest::new(
+            String::from("Back <b>Future</b>"),
+            String::from("cop"),
+            String::new(),
+        );
+
+        let query = request.to_query(&schema()).unwrap();
+
+        let expected = BooleanQuery::from_tokens(
+            vec![
+                Token::new("back".to_string()),
+                Token::new("future".to_string()),
+            ],
+            vec![Token::new("cop".to_string())],
+            vec![],
+        );
+
+        assert_eq!(query, Query::from(expected));
+    }
+
+    #[test]
+    fn can_build_phrase_query() {
+        let request = SearchRequest::new(
+            String::from("\"Back to the <b>Future</b>\" cop \"black cat\"~2 \"Again\""),
+            String::new(),
+            String::from("\"unbalanced"),
+        );
+
+        let query = request.to_query(&schema()).unwrap();
+
+        let phrase =
+            |terms: &[&str]| PhraseQuery::new(terms.iter().map(|term| term.to_string()).collect());
+        let term = |term: &str| Query::from(TermQuery::new(term.to_string()));
+
+        let expected = BooleanQuery::new(
+            vec![
+                term("cop"),
+                Query::from(phrase(&["back", "to", "the", "future"])),
+                Query::from(phrase(&["black", "cat"]).with_slop(2)),
+                term("again"),
+            ],
+            vec![],
+            vec![term("unbalanced")],
+        );
+
+        assert_eq!(query, Query::from(expected));
+    }
+
+    #[test]
+    fn can_build_match_query() {
+        let mut request = SearchRequest::new(String::new(), String::from("cop"), String::new());
+        request.match_query = Some(MatchRequest {
+            query: String::from("Back Future"),
+            operator: Operator::And,
+            field: Some(String::from("title")),
+        });
+
+        let query = request.to_query(&schema()).unwrap();
+
+        let term = |term: &str| Query::from(TermQuery::new(term.to_string()));
+        let title = |term: &str| {
+            Query::from(TermQuery::new(term.to_string()).with_field("title".to_string()))
+        };
+        let expected = BooleanQuery::new(
+            vec![Query::from(BooleanQuery::new(
+                vec![title("back"), title("future")],
+                vec![],
+                vec![],
+            ))],
+            vec![term("cop")],
+            vec![],
+        );
+
+        assert_eq!(query, Query::from(expected));
+    }
+
+    #[test]
+    fn can_deserialize_match_operator() {
+        let parse = |json: &str| serde_json::from_str::<SearchRequest>(json);
+
+        let request = parse(r#"{ "match": { "query": "cop", "operator": "and" } }"#).unwrap();
+
+        assert_eq!(request.match_query.unwrap().operator, Operator::And);
+        assert!(parse(r#"{ "match": { "query": "cop", "operator": "xor" } }"#).is_err());
+    }
+
+    #[test]
+    fn unknown_match_field_fails() {
+        let mut request = SearchRequest::new(String::new(), String::new(), String::new());
+        request.match_query = Some(MatchRequest {
+            query: String::from("Back"),
+            operator: Operator::Or,
+            field: Some(String::from("author")),
+        });
+
+        assert_eq!(
+            request.to_query(&schema()).unwrap_err().to_string(),
+            "Unknown field 'author'."
+        );
+    }
+}
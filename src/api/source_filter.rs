@@ -0,0 +1,211 @@
+use rocket::serde::json::serde_json::{Map, Value};
+use rocket::serde::Deserialize;
+use rocket::FromForm;
+
+/// Which fields of the source of a hit are returned as its `_source`, like Elasticsearch's source
+/// filtering. In a JSON request it is `true` or `false`, a list of fields to include, or
+/// `{ "includes": [...], "excludes": [...] }`. Field patterns can contain `*` wildcards, e.g.
+/// `auth*`.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+pub(crate) enum SourceFilter {
+    Enabled(bool),
+    Includes(Vec<String>),
+    Filter {
+        #[serde(default)]
+        includes: Vec<String>,
+        #[serde(default)]
+        excludes: Vec<String>,
+    },
+}
+
+/// Source filtering as query parameters: `_source=false`, or comma separated field patterns in
+/// `_source_includes` and `_source_excludes`.
+#[derive(Debug, FromForm)]
+pub(crate) struct SourceParams<'r> {
+    #[field(name = "_source")]
+    pub source: Option<bool>,
+    #[field(name = "_source_includes")]
+    pub includes: Option<&'r str>,
+    #[field(name = "_source_excludes")]
+    pub excludes: Option<&'r str>,
+}
+
+impl From<SourceParams<'_>> for SourceFilter {
+    fn from(params: SourceParams<'_>) -> Self {
+        let patterns = |patterns: Option<&str>| -> Vec<String> {
+            patterns
+                .map(|patterns| {
+                    patterns
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        match (params.source, params.includes, params.excludes) {
+            (Some(false), _, _) => SourceFilter::Enabled(false),
+            (_, None, None) => SourceFilter::Enabled(true),
+            _ => SourceFilter::Filter {
+                includes: patterns(params.includes),
+                excludes: patterns(params.excludes),
+            },
+        }
+    }
+}
+
+impl SourceFilter {
+    /// Returns the fields of the source to return, or nothing if the source is disabled. Without
+    /// includes every field is included, and excludes win over includes.
+    pub fn filter(&self, source: Map<String, Value>) -> Option<Map<String, Value>> {
+        let (includes, excludes): (&[String], &[String]) = match self {
+            SourceFilter::Enabled(false) => return None,
+            SourceFilter::Enabled(true) => (&[], &[]),
+            SourceFilter::Includes(includes) => (includes, &[]),
+            SourceFilter::Filter { includes, excludes } => (includes, excludes),
+        };
+
+        Some(
+            source
+                .into_iter()
+                .filter(|(name, _)| {
+                    (includes.is_empty() || includes.iter().any(|p| matches_pattern(p, name)))
+                        && !excludes.iter().any(|p| matches_pattern(p, name))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Default for SourceFilter {
+    fn default() -> Self {
+        SourceFilter::Enabled(true)
+    }
+}
+
+/// Matches a field name against a pattern where `*` matches any number of characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // There is always a first part, which is empty if the pattern starts with `*`.
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+
+    // Without any `*`, the whole name has to match.
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::source_filter::{matches_pattern, SourceFilter, SourceParams};
+    use rocket::serde::json::serde_json::{self, json, Map, Value};
+
+    fn source() -> Map<String, Value> {
+        let source = json!({
+            "author": "Doc",
+            "body": "A cop from the future",
+            "title": "Future Cop",
+        });
+
+        source.as_object().unwrap().clone()
+    }
+
+    fn field_names(filter: &SourceFilter) -> Option<Vec<String>> {
+        filter
+            .filter(source())
+            .map(|fields| fields.keys().cloned().collect())
+    }
+
+    #[test]
+    fn can_match_patterns() {
+        assert!(matches_pattern("title", "title"));
+        assert!(!matches_pattern("title", "titles"));
+        assert!(matches_pattern("tit*", "title"));
+        assert!(matches_pattern("*le", "title"));
+        assert!(matches_pattern("t*t*e", "title"));
+        assert!(!matches_pattern("t*x*e", "title"));
+        assert!(matches_pattern("*", "title"));
+        assert!(!matches_pattern("title*s", "title"));
+    }
+
+    #[test]
+    fn can_filter_source() {
+        assert_eq!(field_names(&SourceFilter::Enabled(false)), None);
+        assert_eq!(
+            field_names(&SourceFilter::default()),
+            Some(vec!["author".into(), "body".into(), "title".into()])
+        );
+        assert_eq!(
+            field_names(&SourceFilter::Includes(vec!["title".into()])),
+            Some(vec!["title".into()])
+        );
+        assert_eq!(
+            field_names(&SourceFilter::Filter {
+                includes: vec!["*".into()],
+                excludes: vec!["b*".into()],
+            }),
+            Some(vec!["author".into(), "title".into()])
+        );
+    }
+
+    #[test]
+    fn can_deserialize() {
+        let filter = |json: &str| serde_json::from_str::<SourceFilter>(json).unwrap();
+
+        assert_eq!(filter("false"), SourceFilter::Enabled(false));
+        assert_eq!(
+            filter(r#"["title"]"#),
+            SourceFilter::Includes(vec!["title".into()])
+        );
+        assert_eq!(
+            filter(r#"{ "excludes": ["body"] }"#),
+            SourceFilter::Filter {
+                includes: vec![],
+                excludes: vec!["body".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn can_build_from_params() {
+        let params = |source, includes, excludes| {
+            SourceFilter::from(SourceParams {
+                source,
+                includes,
+                excludes,
+            })
+        };
+
+        assert_eq!(
+            params(Some(false), Some("title"), None),
+            SourceFilter::Enabled(false)
+        );
+        assert_eq!(params(None, None, None), SourceFilter::Enabled(true));
+        assert_eq!(
+            params(Some(true), Some("title, auth*"), None),
+            SourceFilter::Filter {
+                includes: vec!["title".into(), "auth*".into()],
+                excludes: vec![],
+            }
+        );
+    }
+}
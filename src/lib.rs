@@ -1,30 +1,697 @@
 //! # Rucene
 //!
 //! A very simple, Lucene-like library for full-text search, for educational purposes.
+//!
+//! Like in Lucene, an `IndexWriter` changes the index, and `IndexReader`s search it. A reader is a
+//! point-in-time view of the index: changes only become visible to new readers after a `refresh`,
+//! and readers can be cloned and searched from many threads while the writer goes on.
+//! # Example
+//! ```
+//! use rucene::rucene_internal::query::TermQuery;
+//! use rucene::IndexWriter;
+//!
+//! let mut writer = IndexWriter::new();
+//! let reader = writer.reader();
+//!
+//! writer.index_text("0", "Back to the Future").unwrap();
+//! writer.refresh();
+//!
+//! let future = || TermQuery::new("future".to_string()).into();
+//!
+//! assert!(reader.search(future()).unwrap().is_empty());
+//! assert_eq!(writer.reader().search(future()).unwrap().len(), 1);
+//! ```
 
-use crate::rucene_internal::document::{AnalysedDocument, DocumentResult};
-use crate::rucene_internal::inverted_index::InvertedIndex;
+use crate::rucene_internal::document::{AnalysedDocument, DocumentResult, StoredFields};
+use crate::rucene_internal::inverted_index::{InvertedIndex, Snapshot};
+use crate::rucene_internal::merge_policy::MergePolicy;
 use crate::rucene_internal::query::Query;
+use crate::rucene_internal::schema::{Schema, DEFAULT_FIELD, SOURCE_FIELD};
+use crate::rucene_internal::similarity::{BM25Similarity, Similarity};
+use crate::rucene_internal::store::Directory;
+use crate::rucene_internal::translog::{Durability, Operation, Translog};
 use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
 
 pub mod rucene_internal;
 
-pub struct Rucene {
+/// Adds, updates and deletes documents, and commits them. Searches go through the `IndexReader`s
+/// that the writer hands out.
+pub struct IndexWriter {
     inverted_index: InvertedIndex,
+    schema: Arc<Schema>,
+
+    // Where the index is committed to, unless it only lives in memory.
+    directory: Option<Directory>,
+
+    // Logs the changes since the last commit, when there is a directory.
+    translog: Option<Translog>,
+
+    // The reader of the last refresh.
+    reader: IndexReader,
 }
 
-impl Rucene {
+impl IndexWriter {
     pub fn new() -> Self {
-        Rucene {
-            inverted_index: InvertedIndex::new(),
+        Self::from_index(InvertedIndex::new(), None, None)
+    }
+
+    /// Ranks search results with a `Similarity` other than the default `BM25Similarity`.
+    pub fn with_similarity(similarity: Box<dyn Similarity>) -> Self {
+        Self::from_index(InvertedIndex::with_similarity(similarity), None, None)
+    }
+
+    /// Opens the index that was last committed to the data directory, or an empty index if
+    /// nothing has been committed to it yet, and replays the changes in the translog that came
+    /// after the commit. The directory is created if it does not exist. Corrupt files, and files
+    /// written by an incompatible version, are an error.
+    pub fn open(data_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::open_with_similarity(data_dir, Box::<BM25Similarity>::default())
+    }
+
+    /// Opens the index in the data directory like `open`, ranking search results with a
+    /// `Similarity` other than the default `BM25Similarity`. The similarity is not persisted, so
+    /// it has to be passed every time the index is opened.
+    pub fn open_with_similarity(
+        data_dir: &Path,
+        similarity: Box<dyn Similarity>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let directory = Directory::open(data_dir)?;
+        let mut inverted_index = InvertedIndex::open(&directory, similarity)?;
+
+        let (translog, operations) = Translog::open(&directory, inverted_index.committed_seq_no())?;
+
+        for operation in operations {
+            apply(&mut inverted_index, operation)?;
+        }
+
+        Ok(Self::from_index(
+            inverted_index,
+            Some(directory),
+            Some(translog),
+        ))
+    }
+
+    fn from_index(
+        mut inverted_index: InvertedIndex,
+        directory: Option<Directory>,
+        translog: Option<Translog>,
+    ) -> Self {
+        let schema = Arc::new(Schema::default());
+        let reader = IndexReader {
+            snapshot: Arc::new(inverted_index.snapshot()),
+            schema: schema.clone(),
+        };
+
+        IndexWriter {
+            inverted_index,
+            schema,
+            directory,
+            translog,
+            reader,
+        }
+    }
+
+    /// Writes the index to its data directory, and empties the translog. Changes survive a
+    /// restart once they are in the translog already, but committing keeps the translog short, so
+    /// opening the index has less to replay. An index that only lives in memory has nothing to
+    /// commit to. Committing does not refresh.
+    pub fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+        let (Some(directory), Some(translog)) = (&self.directory, &mut self.translog) else {
+            return Ok(());
+        };
+
+        self.inverted_index.commit(directory, translog.seq_no())?;
+        translog.trim()
+    }
+
+    /// The size of the translog, which grows with every change until the next commit.
+    pub fn translog_size_in_bytes(&self) -> u64 {
+        self.translog
+            .as_ref()
+            .map_or(0, |translog| translog.size_in_bytes())
+    }
+
+    /// Makes the changes so far visible to the readers that the writer hands out from now on,
+    /// without writing them to the data directory like `commit` does. Readers handed out before
+    /// keep seeing the index as it was.
+    pub fn refresh(&mut self) {
+        self.reader = IndexReader {
+            snapshot: Arc::new(self.inverted_index.snapshot()),
+            schema: self.schema.clone(),
+        };
+    }
+
+    /// Returns a reader of the index as of the last refresh.
+    pub fn reader(&self) -> IndexReader {
+        self.reader.clone()
+    }
+
+    /// Indexes text with the fields of the schema, rather than only the default field.
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Arc::new(schema);
+        self.refresh();
+        self
+    }
+
+    /// Merges segments as the merge policy decides, rather than with the default
+    /// `TieredMergePolicy`.
+    pub fn with_merge_policy(mut self, merge_policy: Box<dyn MergePolicy>) -> Self {
+        self.inverted_index.set_merge_policy(merge_policy);
+        self
+    }
+
+    /// Syncs the translog to disk as the durability says, rather than on every change. Indexes that
+    /// only live in memory have no translog.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        if let Some(translog) = &mut self.translog {
+            translog.set_durability(durability);
         }
+        self
+    }
+
+    /// Buffers this many new documents in memory before they are flushed to a new segment.
+    pub fn with_max_buffered_docs(mut self, max_buffered_docs: usize) -> Self {
+        self.inverted_index.set_max_buffered_docs(max_buffered_docs);
+        self
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
     }
 
     pub fn index(&mut self, document: AnalysedDocument) -> Result<(), Box<dyn Error>> {
-        self.inverted_index.index(document)
+        self.log_and_apply(Operation::Index(document))
+    }
+
+    /// Analyses the text as the default field, and indexes the tokens as the document with the id.
+    pub fn index_text(&mut self, id: &str, text: &str) -> Result<(), Box<dyn Error>> {
+        self.index_fields(id, &[(DEFAULT_FIELD, text)])
+    }
+
+    /// Analyses the text of each field with the analyser of the field in the schema, and indexes
+    /// the tokens as the document with the id, replacing any previous version of it.
+    pub fn index_fields(
+        &mut self,
+        id: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<(), Box<dyn Error>> {
+        let document = self.schema.analyse(id, fields)?;
+
+        self.index(document)
+    }
+
+    /// Indexes the fields like `index_fields`, and stores the source of the document in the
+    /// `SOURCE_FIELD`, so that searches can return the document as it was given.
+    pub fn index_fields_with_source(
+        &mut self,
+        id: &str,
+        fields: &[(&str, &str)],
+        source: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut document = self.schema.analyse(id, fields)?;
+        document
+            .stored_fields
+            .insert(SOURCE_FIELD.to_string(), source.to_string());
+
+        self.index(document)
+    }
+
+    /// Merges the fields into the stored fields of the indexed document, and indexes the result as
+    /// the new version of the document. Fields that are not stored can not be analysed again, so
+    /// they are only kept if they are given again. Any source of the document is dropped, since it
+    /// no longer matches the fields.
+    pub fn update_fields(
+        &mut self,
+        id: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<(), Box<dyn Error>> {
+        let stored_fields = self
+            .stored_fields(id)
+            .ok_or_else(|| format!("Document '{0}' is not indexed.", id))?;
+
+        let merged_fields: Vec<(&str, &str)> = stored_fields
+            .iter()
+            .map(|(name, text)| (name.as_str(), text.as_str()))
+            .filter(|(name, _)| *name != SOURCE_FIELD)
+            .filter(|(name, _)| fields.iter().all(|(updated, _)| updated != name))
+            .chain(fields.iter().copied())
+            .collect();
+
+        let document = self.schema.analyse(id, &merged_fields)?;
+
+        self.index(document)
+    }
+
+    /// Returns the stored fields of the document, if it has been indexed, including changes that
+    /// have not been refreshed yet.
+    pub fn stored_fields(&self, id: &str) -> Option<&StoredFields> {
+        self.inverted_index.stored_fields(id)
+    }
+
+    /// Deletes the document, so that it is no longer returned by searches after the next refresh.
+    /// Returns whether the document was indexed and not yet deleted.
+    pub fn delete(&mut self, id: &str) -> Result<bool, Box<dyn Error>> {
+        if self.stored_fields(id).is_none() {
+            return Ok(false);
+        }
+
+        self.log_and_apply(Operation::Delete(id.to_string()))?;
+
+        Ok(true)
+    }
+
+    /// Appends the change to the translog before applying it, so that it is not lost if the
+    /// process crashes before the next commit.
+    fn log_and_apply(&mut self, operation: Operation) -> Result<(), Box<dyn Error>> {
+        if let Some(translog) = &mut self.translog {
+            translog.add(&operation)?;
+        }
+
+        apply(&mut self.inverted_index, operation)
+    }
+
+    /// Merges the whole index into a single segment, which drops the postings of deleted
+    /// documents. Merges that the merge policy decides on also drop them, in the background.
+    pub fn compact(&mut self) {
+        self.inverted_index.compact()
+    }
+
+    /// Waits until the merges that run in the background have finished.
+    pub fn wait_for_merges(&mut self) {
+        self.inverted_index.wait_for_merges()
+    }
+}
+
+fn apply(inverted_index: &mut InvertedIndex, operation: Operation) -> Result<(), Box<dyn Error>> {
+    match operation {
+        Operation::Index(document) => inverted_index.index(document),
+        Operation::Delete(id) => {
+            inverted_index.delete(&id);
+            Ok(())
+        }
+    }
+}
+
+impl Default for IndexWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A point-in-time view of the index, as of the `IndexWriter::refresh` it came from. Readers are
+/// cheap to clone, since clones share the segments, and many threads can search them at once.
+#[derive(Clone)]
+pub struct IndexReader {
+    snapshot: Arc<Snapshot>,
+    schema: Arc<Schema>,
+}
+
+impl IndexReader {
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    pub fn search(&self, query: Query) -> Result<Vec<DocumentResult>, Box<dyn Error>> {
+        self.snapshot.retrieve(query)
+    }
+
+    /// Returns the stored fields of the document, if it is in the view of the reader.
+    pub fn stored_fields(&self, id: &str) -> Option<&StoredFields> {
+        self.snapshot.stored_fields(id)
+    }
+
+    /// The number of documents in the view of the reader.
+    pub fn num_docs(&self) -> usize {
+        self.snapshot.num_docs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::merge_policy::NoMergePolicy;
+    use crate::rucene_internal::query::TermQuery;
+    use crate::rucene_internal::schema::{Field, Schema, SOURCE_FIELD};
+    use crate::rucene_internal::similarity::BooleanSimilarity;
+    use crate::rucene_internal::store::tests::temp_dir;
+    use crate::rucene_internal::translog::{Durability, TRANSLOG_FILE};
+    use crate::{IndexReader, IndexWriter};
+    use std::fs;
+    use std::path::Path;
+    use std::thread;
+
+    fn schema() -> Schema {
+        Schema::new()
+            .with_field(Field::new("title".to_string()))
+            .with_field(Field::new("body".to_string()))
     }
 
-    pub fn search(self, query: Query) -> Result<Vec<DocumentResult>, Box<dyn Error>> {
-        self.inverted_index.retrieve(query)
+    fn init_writer() -> IndexWriter {
+        let mut writer = IndexWriter::new().with_schema(schema());
+        writer
+            .index_fields("0", &[("title", "Back to the Future"), ("body", "Marty")])
+            .unwrap();
+
+        writer
+    }
+
+    fn open_writer(path: &Path) -> IndexWriter {
+        IndexWriter::open(path).unwrap().with_schema(schema())
+    }
+
+    #[test]
+    fn can_reopen_committed_index() {
+        let path = temp_dir();
+
+        let mut writer = open_writer(&path);
+        writer
+            .index_fields("0", &[("title", "Back to the Future"), ("body", "Marty")])
+            .unwrap();
+        writer
+            .index_fields("1", &[("title", "Future Cop")])
+            .unwrap();
+        writer.index_fields("2", &[("title", "Again")]).unwrap();
+        writer.delete("2").unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        let mut writer = open_writer(&path);
+
+        assert_eq!(search_ids(&mut writer, "title", "future"), vec!["1", "0"]);
+        assert_eq!(search_ids(&mut writer, "body", "marty"), vec!["0"]);
+        assert!(search_ids(&mut writer, "title", "again").is_empty());
+        assert_eq!(writer.stored_fields("1").unwrap()["title"], "Future Cop");
+
+        // The reopened index keeps numbering documents after the committed ones, so ties rank
+        // the new document last.
+        writer.index_fields("3", &[("title", "Cop Again")]).unwrap();
+
+        assert_eq!(search_ids(&mut writer, "title", "cop"), vec!["1", "3"]);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn can_open_index_with_similarity() {
+        let path = temp_dir();
+
+        let mut writer = open_writer(&path);
+        writer
+            .index_fields("0", &[("title", "Back to the Future")])
+            .unwrap();
+        writer.commit().unwrap();
+        writer
+            .index_fields("1", &[("title", "Future Cop")])
+            .unwrap();
+        drop(writer);
+
+        // Both the committed and the replayed document are scored with the similarity.
+        let mut writer = IndexWriter::open_with_similarity(&path, Box::new(BooleanSimilarity {}))
+            .unwrap()
+            .with_schema(schema());
+        writer.refresh();
+
+        let results = writer
+            .reader()
+            .search(
+                TermQuery::new("future".to_string())
+                    .with_field("title".to_string())
+                    .into(),
+            )
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.score == 1.0));
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn replays_translog_after_commit() {
+        let path = temp_dir();
+
+        let mut writer = open_writer(&path);
+        writer
+            .index_fields("0", &[("title", "Back to the Future"), ("body", "Marty")])
+            .unwrap();
+        writer.commit().unwrap();
+        assert_eq!(writer.translog_size_in_bytes(), 0);
+
+        // Changes after the commit are only in the translog when the writer goes away.
+        writer
+            .index_fields("1", &[("title", "Future Cop")])
+            .unwrap();
+        writer.update_fields("0", &[("body", "Doc")]).unwrap();
+        writer.delete("1").unwrap();
+        assert!(writer.translog_size_in_bytes() > 0);
+        drop(writer);
+
+        let mut writer = open_writer(&path);
+
+        assert_eq!(search_ids(&mut writer, "title", "future"), vec!["0"]);
+        assert_eq!(search_ids(&mut writer, "body", "doc"), vec!["0"]);
+        assert!(search_ids(&mut writer, "body", "marty").is_empty());
+
+        // Replaying again after a commit changes nothing.
+        writer.commit().unwrap();
+        drop(writer);
+        let mut writer = open_writer(&path);
+
+        assert_eq!(search_ids(&mut writer, "body", "doc"), vec!["0"]);
+        assert_eq!(writer.reader().num_docs(), 1);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn replays_translog_with_truncated_tail() {
+        let path = temp_dir();
+
+        // Without syncing every change, a crash can leave changes that are not complete.
+        let mut writer = open_writer(&path).with_durability(Durability::Async);
+        writer
+            .index_fields("0", &[("title", "Back to the Future")])
+            .unwrap();
+        writer
+            .index_fields("1", &[("title", "Future Cop")])
+            .unwrap();
+        drop(writer);
+
+        // A crash in the middle of appending the second document.
+        let translog_path = path.join(TRANSLOG_FILE);
+        let bytes = fs::read(&translog_path).unwrap();
+        fs::write(&translog_path, &bytes[..bytes.len() - 7]).unwrap();
+
+        let mut writer = open_writer(&path);
+
+        assert_eq!(search_ids(&mut writer, "title", "future"), vec!["0"]);
+
+        // The writer goes on after the last complete change.
+        writer
+            .index_fields("2", &[("title", "Future Again")])
+            .unwrap();
+        drop(writer);
+        let mut writer = open_writer(&path);
+
+        assert_eq!(search_ids(&mut writer, "title", "future"), vec!["2", "0"]);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_index_fails_to_open() {
+        let path = temp_dir();
+
+        let mut writer = open_writer(&path);
+        writer
+            .index_fields("0", &[("title", "Back to the Future")])
+            .unwrap();
+        writer.commit().unwrap();
+
+        let postings_path = path.join("_0.postings");
+        let mut bytes = fs::read(&postings_path).unwrap();
+        let last = bytes.len() - 5;
+        bytes[last] ^= 0xff;
+        fs::write(&postings_path, bytes).unwrap();
+
+        let error = IndexWriter::open(&path).err().unwrap().to_string();
+
+        assert!(error.starts_with("File '_0.postings' is corrupt"));
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    /// Refreshes, and returns the ids of the documents that match the term, in order.
+    fn search_ids(writer: &mut IndexWriter, field: &str, term: &str) -> Vec<String> {
+        writer.refresh();
+
+        reader_ids(&writer.reader(), field, term)
+    }
+
+    /// Returns the ids of the documents that match the term in the reader, in order.
+    fn reader_ids(reader: &IndexReader, field: &str, term: &str) -> Vec<String> {
+        let query = TermQuery::new(term.to_string()).with_field(field.to_string());
+
+        reader
+            .search(query.into())
+            .unwrap()
+            .into_iter()
+            .map(|result| result.id)
+            .collect()
+    }
+
+    #[test]
+    fn readers_see_index_as_of_refresh() {
+        let mut writer = init_writer();
+        writer.refresh();
+        let old_reader = writer.reader();
+
+        writer
+            .index_fields("1", &[("title", "Future Cop")])
+            .unwrap();
+        writer.delete("0").unwrap();
+
+        // The writer sees its own changes, readers only after a refresh.
+        assert!(writer.stored_fields("0").is_none());
+        assert_eq!(reader_ids(&writer.reader(), "title", "future"), vec!["0"]);
+        assert!(writer.reader().stored_fields("1").is_none());
+
+        writer.refresh();
+        let reader = writer.reader();
+
+        assert_eq!(reader_ids(&reader, "title", "future"), vec!["1"]);
+        assert_eq!(reader.num_docs(), 1);
+        assert_eq!(reader.stored_fields("1").unwrap()["title"], "Future Cop");
+
+        // Readers from before the refresh are unchanged.
+        assert_eq!(reader_ids(&old_reader, "title", "future"), vec!["0"]);
+        assert_eq!(old_reader.num_docs(), 1);
+        assert_eq!(old_reader.stored_fields("0").unwrap()["body"], "Marty");
+    }
+
+    #[test]
+    fn refreshing_flushes_buffer() {
+        let mut writer = IndexWriter::new()
+            .with_schema(schema())
+            .with_merge_policy(Box::new(NoMergePolicy {}))
+            .with_max_buffered_docs(10);
+
+        for i in 0..25 {
+            writer
+                .index_fields(&i.to_string(), &[("title", "Back to the Future")])
+                .unwrap();
+
+            if i % 5 == 4 {
+                writer.refresh();
+                assert_eq!(writer.reader().num_docs(), i + 1);
+            }
+        }
+
+        assert_eq!(writer.inverted_index.num_segments(), 5);
+    }
+
+    #[test]
+    fn merges_absorb_refreshed_segments() {
+        let mut writer = init_writer();
+
+        for i in 0..1000 {
+            writer
+                .index_fields(&i.to_string(), &[("title", "Back to the Future")])
+                .unwrap();
+            writer.refresh();
+        }
+
+        writer.inverted_index.wait_for_merges();
+
+        assert_eq!(writer.reader().num_docs(), 1000);
+        assert!(writer.inverted_index.num_segments() <= 20);
+    }
+
+    #[test]
+    fn readers_can_search_from_many_threads() {
+        let mut writer = init_writer();
+        writer
+            .index_fields("1", &[("title", "Future Cop")])
+            .unwrap();
+        writer.refresh();
+
+        let searches: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = writer.reader();
+                thread::spawn(move || reader_ids(&reader, "title", "future"))
+            })
+            .collect();
+
+        // The writer goes on while the readers search.
+        writer.delete("1").unwrap();
+
+        for search in searches {
+            assert_eq!(search.join().unwrap(), vec!["1", "0"]);
+        }
+    }
+
+    #[test]
+    fn indexing_again_replaces_document() {
+        let mut writer = init_writer();
+
+        writer
+            .index_fields("0", &[("title", "Future Cop")])
+            .unwrap();
+
+        assert!(search_ids(&mut writer, "title", "back").is_empty());
+        assert!(search_ids(&mut writer, "body", "marty").is_empty());
+        assert_eq!(search_ids(&mut writer, "title", "cop"), vec!["0"]);
+        assert_eq!(writer.stored_fields("0").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn can_update_fields() {
+        let mut writer = init_writer();
+
+        writer
+            .update_fields("0", &[("title", "Future Cop")])
+            .unwrap();
+
+        assert!(search_ids(&mut writer, "title", "back").is_empty());
+        assert_eq!(search_ids(&mut writer, "title", "cop"), vec!["0"]);
+        assert_eq!(search_ids(&mut writer, "body", "marty"), vec!["0"]);
+        assert_eq!(writer.stored_fields("0").unwrap()["title"], "Future Cop");
+        assert_eq!(writer.stored_fields("0").unwrap()["body"], "Marty");
+
+        assert_eq!(
+            writer
+                .update_fields("1", &[("title", "Again")])
+                .unwrap_err()
+                .to_string(),
+            "Document '1' is not indexed."
+        );
+
+        // A failed update leaves the document as it was.
+        assert!(writer.update_fields("0", &[("author", "Doc")]).is_err());
+        assert_eq!(search_ids(&mut writer, "title", "cop"), vec!["0"]);
+    }
+
+    #[test]
+    fn can_store_source() {
+        let mut writer = IndexWriter::new().with_schema(schema());
+        let source = r#"{"title":"Future Cop","year":1985}"#;
+
+        writer
+            .index_fields_with_source("0", &[("title", "Future Cop")], source)
+            .unwrap();
+
+        assert_eq!(search_ids(&mut writer, "title", "cop"), vec!["0"]);
+        assert_eq!(writer.stored_fields("0").unwrap()[SOURCE_FIELD], source);
+
+        // An update drops the source, which no longer matches.
+        writer
+            .update_fields("0", &[("title", "Back to the Future")])
+            .unwrap();
+
+        assert!(!writer
+            .stored_fields("0")
+            .unwrap()
+            .contains_key(SOURCE_FIELD));
     }
 }
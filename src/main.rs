@@ -1,50 +1,352 @@
 #[macro_use]
 extern crate rocket;
 
-use crate::api::analyser::Analyser;
 use crate::api::index_document::IndexDocument;
-use crate::api::responses::IndexResponse;
-use ::rucene::Rucene;
+use crate::api::responses::{IndexResponse, SearchHit, SearchResponse};
+use crate::api::search_request::SearchRequest;
+use crate::api::source_filter::{SourceFilter, SourceParams};
+use ::rucene::rucene_internal::document::DocumentResult;
+use ::rucene::rucene_internal::merge_policy::{
+    LogByteSizeMergePolicy, MergePolicy, TieredMergePolicy,
+};
+use ::rucene::rucene_internal::query::Operator;
+use ::rucene::rucene_internal::query_parser::QueryParser;
+use ::rucene::rucene_internal::schema::{Field, Schema, DEFAULT_FIELD};
+use ::rucene::rucene_internal::similarity::{
+    BM25Similarity, BooleanSimilarity, ClassicSimilarity, LMDirichletSimilarity, Similarity,
+};
+use ::rucene::rucene_internal::simple_query_parser::{SimpleQueryFlags, SimpleQueryParser};
+use ::rucene::rucene_internal::translog::Durability;
+use ::rucene::{IndexReader, IndexWriter};
 use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::State;
-use std::sync::Mutex;
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
 mod api;
 
+const DEFAULT_DATA_DIR: &str = "data";
+
+// The index is committed once the translog has grown this large, so that replaying it on startup
+// stays short.
+const TRANSLOG_FLUSH_THRESHOLD_BYTES: u64 = 64 * 1024 * 1024;
+
+const DEFAULT_TRANSLOG_SYNC_INTERVAL_MS: u64 = 5000;
+
+/// Indexes a document, replacing any document with the same id. An id is generated if the
+/// document has none, and returned either way. Like the other changes, it is written to the
+/// translog and visible to searches before responding.
 #[post("/index_doc", format = "json", data = "<document>")]
 fn index_doc(
     document: Json<IndexDocument>,
-    rucene: &State<Mutex<Rucene>>,
-    analyser: &State<Analyser>,
+    writer: &State<Mutex<IndexWriter>>,
+    reader: &State<RwLock<IndexReader>>,
+) -> Result<Json<IndexResponse>, Status> {
+    let id = document.id_or_generate();
+    let mut writer = writer.lock().unwrap();
+    let result = index(&mut writer, &id, &document).and_then(|_| refresh(&mut writer, reader));
+
+    Ok(index_response(id, result))
+}
+
+/// Merges the fields into the source of an indexed document, e.g.
+/// `{ "id": "back-to-the-future", "title": "Back to the Future Part II" }` only changes the title.
+#[post("/update_doc", format = "json", data = "<document>")]
+fn update_doc(
+    document: Json<IndexDocument>,
+    writer: &State<Mutex<IndexWriter>>,
+    reader: &State<RwLock<IndexReader>>,
+) -> Result<Json<IndexResponse>, Status> {
+    let Some(id) = document.id() else {
+        return Ok(index_response(
+            String::new(),
+            Err("An update needs the id of the document.".into()),
+        ));
+    };
+
+    let mut document = document.into_inner();
+    let mut writer = writer.lock().unwrap();
+
+    let result = match writer.stored_fields(&id) {
+        Some(stored_fields) => {
+            document.merge(stored_fields);
+            index(&mut writer, &id, &document).and_then(|_| refresh(&mut writer, reader))
+        }
+        None => Err(format!("Document '{0}' is not indexed.", id).into()),
+    };
+
+    Ok(index_response(id, result))
+}
+
+/// Indexes the fields of the schema in the source of the document, and stores the source.
+fn index(
+    writer: &mut IndexWriter,
+    id: &str,
+    document: &IndexDocument,
+) -> Result<(), Box<dyn Error>> {
+    let fields = document.fields(writer.schema());
+    let fields: Vec<(&str, &str)> = fields
+        .iter()
+        .map(|(name, text)| (*name, text.as_str()))
+        .collect();
+
+    writer.index_fields_with_source(id, &fields, &document.source_json())
+}
+
+/// Deletes the document with the id. Ids that are not valid in a path, like URLs, must be
+/// percent-encoded.
+#[delete("/doc/<id>")]
+fn delete_doc(
+    id: String,
+    writer: &State<Mutex<IndexWriter>>,
+    reader: &State<RwLock<IndexReader>>,
 ) -> Result<Json<IndexResponse>, Status> {
-    let analysed_doc = analyser.analyse(&document);
+    let mut writer = writer.lock().unwrap();
+    let result = match writer.delete(&id) {
+        Ok(true) => refresh(&mut writer, reader),
+        Ok(false) => Err(format!("Document '{0}' is not indexed.", id).into()),
+        Err(err) => Err(err),
+    };
+
+    Ok(index_response(id, result))
+}
+
+/// Hands a refreshed reader to the searches that start from now on. Changes survive a restart
+/// once they are in the translog, so the index is only committed when the translog is large.
+fn refresh(writer: &mut IndexWriter, reader: &RwLock<IndexReader>) -> Result<(), Box<dyn Error>> {
+    if writer.translog_size_in_bytes() >= TRANSLOG_FLUSH_THRESHOLD_BYTES {
+        writer.commit()?;
+    }
+
+    writer.refresh();
+
+    *reader.write().unwrap() = writer.reader();
+
+    Ok(())
+}
 
-    match rucene.lock().unwrap().index(analysed_doc) {
-        Ok(_) => Ok(Json(IndexResponse {
+fn index_response(id: String, result: Result<(), Box<dyn Error>>) -> Json<IndexResponse> {
+    match result {
+        Ok(_) => Json(IndexResponse {
             success: true,
             error: String::new(),
-        })),
-        Err(err) => Ok(Json(IndexResponse {
+            id,
+        }),
+        Err(err) => Json(IndexResponse {
             success: false,
             error: err.to_string(),
-        })),
+            id,
+        }),
     }
 }
 
-#[get("/search")]
-fn search() {
-    todo!()
+/// Searches with a query string in Lucene's syntax, e.g. `/search?q=%2Bcat%20-dog`.
+/// `_source=false` leaves out the stored fields of hits, and `_source_includes` and
+/// `_source_excludes` pick them, e.g. `_source_includes=title,author`.
+#[get("/search?<q>&<source..>", rank = 1)]
+fn search_query_string(
+    q: &str,
+    source: SourceParams<'_>,
+    reader: &State<RwLock<IndexReader>>,
+) -> Result<Json<SearchResponse>, Status> {
+    let source_filter = SourceFilter::from(source);
+
+    // Searches run on a clone of the reader, so they do not hold the lock, nor wait for writes.
+    let reader = reader.read().unwrap().clone();
+    let query = QueryParser::new(reader.schema(), DEFAULT_FIELD.to_string()).parse(q);
+
+    match query {
+        Ok(query) => Ok(search_response(reader.search(query), &source_filter)),
+        Err(err) => Ok(search_response(Err(err.into()), &source_filter)),
+    }
+}
+
+/// Searches with a forgiving query string meant for search boxes, e.g.
+/// `/search?simple_q=%22black%20cat%22%20-dog`. Malformed input never results in an error.
+/// `default_operator` is `or` or `and`, and `flags` enables operators, e.g. `AND|NOT|PHRASE`.
+/// `field` is the field to search, the default field if not given. Stored fields are filtered
+/// like for `q`.
+#[get(
+    "/search?<simple_q>&<default_operator>&<flags>&<field>&<source..>",
+    rank = 2
+)]
+fn search_simple_query_string(
+    simple_q: &str,
+    default_operator: Option<&str>,
+    flags: Option<&str>,
+    field: Option<&str>,
+    source: SourceParams<'_>,
+    reader: &State<RwLock<IndexReader>>,
+) -> Result<Json<SearchResponse>, Status> {
+    let source_filter = SourceFilter::from(source);
+
+    let reader = reader.read().unwrap().clone();
+    let field_name = field.unwrap_or(DEFAULT_FIELD);
+
+    let field = match reader.schema().field(field_name) {
+        Some(field) if field.indexed => field,
+        _ => {
+            return Ok(search_response(
+                Err(format!("Field '{0}' is not an indexed field.", field_name).into()),
+                &source_filter,
+            ))
+        }
+    };
+
+    let default_operator = match default_operator.map(str::parse).transpose() {
+        Ok(default_operator) => default_operator.unwrap_or(Operator::Or),
+        Err(err) => return Ok(search_response(Err(err), &source_filter)),
+    };
+
+    let query = SimpleQueryParser::new(field)
+        .with_default_operator(default_operator)
+        .with_flags(flags.map(SimpleQueryFlags::parse).unwrap_or_default())
+        .parse(simple_q);
+
+    Ok(search_response(reader.search(query), &source_filter))
+}
+
+#[get("/search", format = "json", data = "<request>", rank = 3)]
+fn search(
+    request: Json<SearchRequest>,
+    reader: &State<RwLock<IndexReader>>,
+) -> Result<Json<SearchResponse>, Status> {
+    let reader = reader.read().unwrap().clone();
+
+    match request.to_query(reader.schema()) {
+        Ok(query) => Ok(search_response(reader.search(query), &request.source)),
+        Err(err) => Ok(search_response(Err(err), &request.source)),
+    }
+}
+
+fn search_response(
+    results: Result<Vec<DocumentResult>, Box<dyn Error>>,
+    source_filter: &SourceFilter,
+) -> Json<SearchResponse> {
+    match results {
+        Ok(results) => Json(SearchResponse {
+            success: true,
+            error: String::new(),
+            hits: results
+                .iter()
+                .map(|result| SearchHit::new(result, source_filter))
+                .collect(),
+        }),
+        Err(err) => Json(SearchResponse {
+            success: false,
+            error: err.to_string(),
+            hits: vec![],
+        }),
+    }
+}
+
+/// Documents have a title, an author and a body, which can be searched separately.
+fn schema() -> Schema {
+    Schema::new()
+        .with_field(Field::new(String::from("title")))
+        .with_field(Field::new(String::from("author")))
+        .with_field(Field::new(DEFAULT_FIELD.to_string()))
+}
+
+/// The merge policy with the name, as configured with `merge_policy`.
+fn merge_policy(name: &str) -> Box<dyn MergePolicy> {
+    match name {
+        "tiered" => Box::<TieredMergePolicy>::default(),
+        "log_byte_size" => Box::<LogByteSizeMergePolicy>::default(),
+        _ => panic!(
+            "Unknown merge policy '{0}', should be 'tiered' or 'log_byte_size'.",
+            name
+        ),
+    }
+}
+
+/// The similarity with the name, as configured with `similarity`.
+fn similarity(name: &str) -> Box<dyn Similarity> {
+    match name {
+        "bm25" => Box::<BM25Similarity>::default(),
+        "classic" => Box::<ClassicSimilarity>::default(),
+        "boolean" => Box::<BooleanSimilarity>::default(),
+        "lm_dirichlet" => Box::<LMDirichletSimilarity>::default(),
+        _ => panic!(
+            "Unknown similarity '{0}', should be 'bm25', 'classic', 'boolean' or 'lm_dirichlet'.",
+            name
+        ),
+    }
+}
+
+/// The durability with the name, as configured with `translog_durability`. Intervals shorter
+/// than `MIN_SYNC_INTERVAL` are raised to it, but an interval of zero is a mistake.
+fn durability(name: &str, sync_interval: Duration) -> Durability {
+    match name {
+        "request" => Durability::Request,
+        "interval" if sync_interval.is_zero() => {
+            panic!("The translog sync interval should be more than 0 ms.")
+        }
+        "interval" => Durability::Interval(sync_interval),
+        "async" => Durability::Async,
+        _ => panic!(
+            "Unknown translog durability '{0}', should be 'request', 'interval' or 'async'.",
+            name
+        ),
+    }
 }
 
 #[rocket::main]
 #[allow(unused_must_use)]
 async fn main() {
-    rocket::build()
-        .manage(Mutex::new(Rucene::new()))
-        .manage(Analyser::standard())
-        .mount("/", routes![index_doc])
-        .mount("/", routes![search])
+    let rocket = rocket::build();
+
+    // The index is kept in `data_dir`, which is configured like any Rocket setting, e.g. with
+    // `ROCKET_DATA_DIR=/var/lib/rucene`.
+    let data_dir: String = rocket
+        .figment()
+        .extract_inner("data_dir")
+        .unwrap_or_else(|_| DEFAULT_DATA_DIR.to_string());
+
+    // Segments are merged with the `merge_policy`, e.g. `ROCKET_MERGE_POLICY=log_byte_size`.
+    let merge_policy_name: String = rocket
+        .figment()
+        .extract_inner("merge_policy")
+        .unwrap_or_else(|_| "tiered".to_string());
+
+    // Search results are ranked with the `similarity`, e.g. `ROCKET_SIMILARITY=classic`.
+    let similarity_name: String = rocket
+        .figment()
+        .extract_inner("similarity")
+        .unwrap_or_else(|_| "bm25".to_string());
+
+    // The translog is synced as `translog_durability` says, e.g.
+    // `ROCKET_TRANSLOG_DURABILITY=interval` with `ROCKET_TRANSLOG_SYNC_INTERVAL_MS=1000`.
+    let durability_name: String = rocket
+        .figment()
+        .extract_inner("translog_durability")
+        .unwrap_or_else(|_| "request".to_string());
+    let sync_interval_ms: u64 = rocket
+        .figment()
+        .extract_inner("translog_sync_interval_ms")
+        .unwrap_or(DEFAULT_TRANSLOG_SYNC_INTERVAL_MS);
+
+    let writer =
+        IndexWriter::open_with_similarity(Path::new(&data_dir), similarity(&similarity_name))
+            .unwrap_or_else(|err| panic!("Could not open the index in '{0}': {1}", data_dir, err))
+            .with_schema(schema())
+            .with_merge_policy(merge_policy(&merge_policy_name))
+            .with_durability(durability(
+                &durability_name,
+                Duration::from_millis(sync_interval_ms),
+            ));
+
+    rocket
+        .manage(RwLock::new(writer.reader()))
+        .manage(Mutex::new(writer))
+        .mount("/", routes![index_doc, update_doc, delete_doc])
+        .mount(
+            "/",
+            routes![search, search_query_string, search_simple_query_string],
+        )
         .launch()
         .await;
 }
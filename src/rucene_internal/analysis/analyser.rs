@@ -0,0 +1,150 @@
+use crate::rucene_internal::analysis::{
+    CharacterFilter, HTMLCharacterFilter, LowerCaseTokenFilter, OffsetCorrections,
+    StandardTokenizer, TokenFilter, Tokenizer,
+};
+use crate::rucene_internal::document::AnalysedDocument;
+use crate::rucene_internal::token::{Token, Tokens};
+
+/// An analysis chain: character filters, then a tokenizer, then token filters.
+pub struct Analyser {
+    character_filters: Vec<Box<dyn CharacterFilter>>,
+    tokenizer: Box<dyn Tokenizer>,
+    token_filters: Vec<Box<dyn TokenFilter>>,
+}
+
+impl Analyser {
+    pub fn new(
+        character_filters: Vec<Box<dyn CharacterFilter>>,
+        tokenizer: Box<dyn Tokenizer>,
+        token_filters: Vec<Box<dyn TokenFilter>>,
+    ) -> Self {
+        Self {
+            character_filters,
+            tokenizer,
+            token_filters,
+        }
+    }
+
+    pub fn standard() -> Self {
+        Self::new(
+            vec![Box::new(HTMLCharacterFilter {})],
+            Box::new(StandardTokenizer {}),
+            vec![Box::new(LowerCaseTokenFilter {})],
+        )
+    }
+
+    /// Analysis is composed of three steps: character filtering, tokenization, and token filtering.
+    pub fn analyse(&self, id: &str, text: &str) -> AnalysedDocument {
+        AnalysedDocument::new(id.to_string(), self.analyse_text(text))
+    }
+
+    /// Runs free text through the same analysis chain as documents, e.g. for search requests.
+    /// The offsets of the tokens point into `text`, before character filtering.
+    pub fn analyse_text(&self, text: &str) -> Tokens {
+        let mut text = text.to_string();
+
+        let corrections = self.run_character_filters(&mut text);
+        let mut tokens = self.run_tokenizer(&text);
+        correct_offsets(&mut tokens, &corrections);
+        self.run_token_filters(&mut tokens);
+
+        tokens
+    }
+
+    /// Returns the offset corrections of each character filter, in the order they ran.
+    fn run_character_filters(&self, input: &mut String) -> Vec<OffsetCorrections> {
+        self.character_filters
+            .iter()
+            .map(|filter| {
+                let mut corrections = OffsetCorrections::default();
+                (*filter).filter(input, &mut corrections);
+                corrections
+            })
+            .collect()
+    }
+
+    fn run_tokenizer(&self, input: &str) -> Vec<Token> {
+        (*self.tokenizer).tokenize(input)
+    }
+
+    fn run_token_filters(&self, input: &mut Vec<Token>) {
+        for filter in &self.token_filters {
+            (*filter).filter(input);
+        }
+    }
+}
+
+/// Maps the offsets of the tokens back through each character filter, the last one first. An end
+/// offset is corrected from the last byte of its token, so that text that a filter removed right
+/// after the token is not counted in it.
+fn correct_offsets(tokens: &mut [Token], corrections: &[OffsetCorrections]) {
+    for offsets in tokens.iter_mut().filter_map(|token| token.offsets.as_mut()) {
+        for corrections in corrections.iter().rev() {
+            offsets.end = match offsets.end > offsets.start {
+                true => corrections.correct(offsets.end - 1) + 1,
+                false => corrections.correct(offsets.end),
+            };
+            offsets.start = corrections.correct(offsets.start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::analysis::{
+        Analyser, HTMLCharacterFilter, LowerCaseTokenFilter, StandardTokenizer,
+    };
+    use crate::rucene_internal::document::AnalysedDocument;
+    use crate::rucene_internal::token::{Offsets, Token};
+
+    fn init_analyser() -> Analyser {
+        Analyser::new(
+            vec![Box::new(HTMLCharacterFilter {})],
+            Box::new(StandardTokenizer {}),
+            vec![Box::new(LowerCaseTokenFilter {})],
+        )
+    }
+
+    #[test]
+    fn can_analyse() {
+        let analyser = init_analyser();
+        let text = "<h1>The Brown’s fiftieth wedding anniversary, at Café Olé.</h1>";
+
+        let expected_doc = AnalysedDocument::new(
+            "0".to_string(),
+            vec![
+                Token::new("the".to_string()),
+                Token::new("brown’s".to_string()),
+                Token::new("fiftieth".to_string()),
+                Token::new("wedding".to_string()),
+                Token::new("anniversary".to_string()),
+                Token::new("at".to_string()),
+                Token::new("café".to_string()),
+                Token::new("olé".to_string()),
+            ],
+        );
+
+        let result = analyser.analyse("0", text);
+
+        assert_eq!(result, expected_doc);
+    }
+
+    #[test]
+    fn offsets_point_into_text_before_filtering() {
+        let analyser = init_analyser();
+        let text = "<h1>Café</h1> <i>Olé</i>";
+
+        let offsets: Vec<Offsets> = analyser
+            .analyse_text(text)
+            .iter()
+            .map(|token| token.offsets.unwrap())
+            .collect();
+
+        assert_eq!(
+            offsets,
+            vec![Offsets { start: 4, end: 9 }, Offsets { start: 18, end: 22 }]
+        );
+        assert_eq!(&text[4..9], "Café");
+        assert_eq!(&text[18..22], "Olé");
+    }
+}
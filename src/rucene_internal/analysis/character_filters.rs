@@ -0,0 +1,102 @@
+use regex::Regex;
+
+/// During the first step, character filtering, the characters of text fields can be adjusted or filtered in various ways.
+/// A good example is HTMLStripCharFilter, which takes HTML as input and returns only the text contained within the HTML and not the HTML tags.
+pub trait CharacterFilter
+where
+    Self: Send + Sync,
+{
+    /// Filtering is done in-place. Filters that remove, add or replace text record where in
+    /// `corrections`, so that the offsets of tokens point into the text before filtering.
+    fn filter(&self, input: &mut String, corrections: &mut OffsetCorrections);
+}
+
+/// Maps byte offsets of the text that a character filter returns to byte offsets of the text it
+/// was given, like Lucene's `BaseCharFilter`.
+#[derive(Debug, Default, Clone)]
+pub struct OffsetCorrections {
+    // The offsets of the filtered text from which on the offsets differ, in order, and the
+    // difference from then on.
+    corrections: Vec<(usize, isize)>,
+}
+
+impl OffsetCorrections {
+    /// Records that from `offset` of the filtered text on, the text before filtering was `diff`
+    /// bytes longer, counting every change before.
+    pub fn add(&mut self, offset: usize, diff: isize) {
+        match self.corrections.last_mut() {
+            Some(last) if last.0 == offset => last.1 = diff,
+            _ => self.corrections.push((offset, diff)),
+        }
+    }
+
+    /// Returns the offset of the text before filtering that `offset` of the filtered text comes
+    /// from.
+    pub fn correct(&self, offset: usize) -> usize {
+        match self
+            .corrections
+            .partition_point(|(from, _)| *from <= offset)
+        {
+            0 => offset,
+            i => (offset as isize + self.corrections[i - 1].1) as usize,
+        }
+    }
+}
+/// Removes HTML tags from the input.
+pub struct HTMLCharacterFilter {}
+
+impl CharacterFilter for HTMLCharacterFilter {
+    fn filter(&self, input: &mut String, corrections: &mut OffsetCorrections) {
+        // I know, I know, you should not use regex to parse HTML.
+        // In this case though, we just want to strip them out, so in this limited use case it
+        // should be fine.
+        // The alternative is to use a library like scraper, which is a great library but not worth
+        // the additional dependency.
+        let re = Regex::new(r"(<[^>]*>)").unwrap();
+
+        let mut result = String::with_capacity(input.len());
+        let mut start = 0;
+
+        for tag in re.find_iter(input) {
+            result.push_str(&input[start..tag.start()]);
+            start = tag.end();
+
+            corrections.add(result.len(), (start - result.len()) as isize);
+        }
+        result.push_str(&input[start..]);
+
+        *input = result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::analysis::{
+        CharacterFilter, HTMLCharacterFilter, OffsetCorrections,
+    };
+
+    #[test]
+    fn html_char_filter() {
+        // Example sentence taken from Relevant Search by Doug Turnbull and Jerry Berryman.
+        let mut source =
+            "<h1>The Brown’s fiftieth wedding anniversary, at Café Olé.</h1>".to_string();
+        let expected = "The Brown’s fiftieth wedding anniversary, at Café Olé.".to_string();
+
+        HTMLCharacterFilter {}.filter(&mut source, &mut OffsetCorrections::default());
+
+        assert_eq!(source, expected);
+    }
+
+    #[test]
+    fn html_char_filter_corrects_offsets() {
+        let mut source = "<p>Back to the <b>Future</b></p>".to_string();
+        let mut corrections = OffsetCorrections::default();
+
+        HTMLCharacterFilter {}.filter(&mut source, &mut corrections);
+
+        assert_eq!(source, "Back to the Future");
+        assert_eq!(corrections.correct(0), 3);
+        assert_eq!(corrections.correct(5), 8);
+        assert_eq!(corrections.correct(12), 18);
+    }
+}
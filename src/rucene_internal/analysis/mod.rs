@@ -0,0 +1,51 @@
+//! Analysis turns text into the tokens that are indexed and searched for. An `Analyser` runs
+//! character filters, a tokenizer and token filters, in that order.
+//! # Example
+//! An analyser that drops stop words, used to index raw text:
+//! ```
+//! use rucene::rucene_internal::analysis::{
+//!     Analyser, HTMLCharacterFilter, LowerCaseTokenFilter, StandardTokenizer, TokenFilter,
+//! };
+//! use rucene::rucene_internal::query::{Query, TermQuery};
+//! use rucene::rucene_internal::schema::{Field, Schema, DEFAULT_FIELD};
+//! use rucene::rucene_internal::token::Token;
+//! use rucene::IndexWriter;
+//!
+//! struct StopWordTokenFilter {}
+//!
+//! impl TokenFilter for StopWordTokenFilter {
+//!     fn filter(&self, tokens: &mut Vec<Token>) {
+//!         tokens.retain(|token| !["the", "to"].contains(&token.value.as_str()));
+//!     }
+//! }
+//!
+//! let analyser = Analyser::new(
+//!     vec![Box::new(HTMLCharacterFilter {})],
+//!     Box::new(StandardTokenizer {}),
+//!     vec![Box::new(LowerCaseTokenFilter {}), Box::new(StopWordTokenFilter {})],
+//! );
+//!
+//! let schema = Schema::new().with_field(Field::new(DEFAULT_FIELD.to_string()).with_analyser(analyser));
+//!
+//! let mut writer = IndexWriter::new().with_schema(schema);
+//! writer.index_text("0", "<b>Back to the Future</b>").unwrap();
+//!
+//! let future = Query::from(TermQuery::new("future".to_string()));
+//! let the = Query::from(TermQuery::new("the".to_string()));
+//!
+//! writer.refresh();
+//! let reader = writer.reader();
+//!
+//! assert_eq!(reader.search(future).unwrap().len(), 1);
+//! assert!(reader.search(the).unwrap().is_empty());
+//! ```
+
+mod analyser;
+mod character_filters;
+mod token_filters;
+mod tokenizers;
+
+pub use analyser::Analyser;
+pub use character_filters::{CharacterFilter, HTMLCharacterFilter, OffsetCorrections};
+pub use token_filters::{LowerCaseTokenFilter, TokenFilter};
+pub use tokenizers::{StandardTokenizer, Tokenizer};
@@ -1,7 +1,7 @@
-use rucene::rucene_internal::token::Token;
+use crate::rucene_internal::token::Token;
 
 /// The final step is token filtering. Here the stream of tokens can be adjusted, either by adding or removing the tokens or by changing them.
-pub(crate) trait TokenFilter
+pub trait TokenFilter
 where
     Self: Send + Sync,
 {
@@ -9,22 +9,25 @@ where
     fn filter(&self, tokens: &mut Vec<Token>);
 }
 
-pub(crate) struct LowerCaseTokenFilter {}
+pub struct LowerCaseTokenFilter {}
 
 /// Lowercases each token.
 impl TokenFilter for LowerCaseTokenFilter {
     fn filter(&self, tokens: &mut Vec<Token>) {
         *tokens = tokens
             .iter()
-            .map(|token| Token::new(token.value.to_lowercase()))
+            .map(|token| Token {
+                value: token.value.to_lowercase(),
+                ..token.clone()
+            })
             .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::api::token_filters::{LowerCaseTokenFilter, TokenFilter};
-    use rucene::rucene_internal::token::Token;
+    use crate::rucene_internal::analysis::{LowerCaseTokenFilter, TokenFilter};
+    use crate::rucene_internal::token::Token;
 
     #[test]
     fn lowercase_token_filter() {
@@ -0,0 +1,80 @@
+use crate::rucene_internal::token::Token;
+use regex::Regex;
+
+/// The next step is tokenization. As the name indicates, during this step raw text is converted into a stream of tokens.
+/// There can be only one tokenizer in any given analysis chain.
+pub trait Tokenizer
+where
+    Self: Send + Sync,
+{
+    /// Returns the tokens of the input, with their byte offsets in it if the tokenizer keeps track
+    /// of them. The `Analyser` corrects these offsets for the character filters that ran before.
+    fn tokenize(&self, input: &str) -> Vec<Token>;
+}
+
+/// Splits on whitespace and punctuation, keeping track of the offsets of each word.
+pub struct StandardTokenizer {}
+
+impl Tokenizer for StandardTokenizer {
+    fn tokenize(&self, input: &str) -> Vec<Token> {
+        let re = Regex::new(r"\s|\.|,|-").unwrap();
+
+        let mut tokens = vec![];
+        let mut start = 0;
+
+        let separators = re
+            .find_iter(input)
+            .map(|separator| (separator.start(), separator.end()));
+
+        for (end, next_start) in separators.chain([(input.len(), input.len())]) {
+            if start < end {
+                tokens.push(Token::with_offsets(
+                    input[start..end].to_string(),
+                    start,
+                    end,
+                ));
+            }
+
+            start = next_start;
+        }
+
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::analysis::{StandardTokenizer, Tokenizer};
+    use crate::rucene_internal::token::{Offsets, Token};
+
+    #[test]
+    fn standard_tokenizer() {
+        // Example sentence taken from Relevant Search by Doug Turnbull and Jerry Berryman.
+        let source = "The Brown’s fiftieth wedding anniversary, at Cafe Ole.";
+        let expected = vec![
+            Token::new("The".to_string()),
+            Token::new("Brown’s".to_string()),
+            Token::new("fiftieth".to_string()),
+            Token::new("wedding".to_string()),
+            Token::new("anniversary".to_string()),
+            Token::new("at".to_string()),
+            Token::new("Cafe".to_string()),
+            Token::new("Ole".to_string()),
+        ];
+
+        let result = StandardTokenizer {}.tokenize(source);
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn standard_tokenizer_offsets() {
+        let source = "Café, Olé";
+
+        let result = StandardTokenizer {}.tokenize(source);
+
+        assert_eq!(result[0].offsets, Some(Offsets { start: 0, end: 5 }));
+        assert_eq!(result[1].offsets, Some(Offsets { start: 7, end: 11 }));
+        assert_eq!(&source[7..11], "Olé");
+    }
+}
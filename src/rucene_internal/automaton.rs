@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// A deterministic automaton over characters, that can be intersected with a sorted term
+/// dictionary to find the terms it accepts without checking every term.
+pub(crate) trait Automaton {
+    type State: Clone;
+
+    fn start(&self) -> Self::State;
+
+    /// Whether the automaton accepts the characters that lead to the state.
+    fn is_match(&self, state: &Self::State) -> bool;
+
+    /// Whether any continuation of the characters that lead to the state can still be accepted.
+    fn can_match(&self, state: &Self::State) -> bool;
+
+    fn step(&self, state: &Self::State, c: char) -> Self::State;
+}
+
+/// Accepts the strings within `max_distance` edits of the query string, where an edit inserts,
+/// deletes or substitutes a character. With `transpositions`, swapping two adjacent characters
+/// also counts as a single edit (the optimal string alignment distance).
+pub(crate) struct LevenshteinAutomaton {
+    query: Vec<char>,
+    max_distance: u32,
+    transpositions: bool,
+}
+
+/// A state is a row of the edit distance matrix between the query and the input so far.
+#[derive(Debug, Clone)]
+pub(crate) struct LevenshteinState {
+    row: Vec<u32>,
+
+    // Needed to detect transpositions.
+    previous_row: Option<Vec<u32>>,
+    previous_char: Option<char>,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(query: &str, max_distance: u32, transpositions: bool) -> Self {
+        LevenshteinAutomaton {
+            query: query.chars().collect(),
+            max_distance,
+            transpositions,
+        }
+    }
+
+    /// The edit distance between the query and the input that lead to the state, if the state
+    /// is a match.
+    pub fn distance(&self, state: &LevenshteinState) -> u32 {
+        state.row[self.query.len()]
+    }
+}
+
+impl Automaton for LevenshteinAutomaton {
+    type State = LevenshteinState;
+
+    fn start(&self) -> LevenshteinState {
+        LevenshteinState {
+            row: (0..=self.query.len() as u32).collect(),
+            previous_row: None,
+            previous_char: None,
+        }
+    }
+
+    fn is_match(&self, state: &LevenshteinState) -> bool {
+        self.distance(state) <= self.max_distance
+    }
+
+    fn can_match(&self, state: &LevenshteinState) -> bool {
+        state
+            .row
+            .iter()
+            .any(|distance| *distance <= self.max_distance)
+    }
+
+    fn step(&self, state: &LevenshteinState, c: char) -> LevenshteinState {
+        let mut row = vec![state.row[0] + 1];
+
+        for (i, query_char) in self.query.iter().enumerate() {
+            let substitution = state.row[i] + u32::from(*query_char != c);
+            let insertion = state.row[i + 1] + 1;
+            let deletion = row[i] + 1;
+
+            let mut distance = substitution.min(insertion).min(deletion);
+
+            if self.transpositions && i > 0 {
+                if let (Some(previous_row), Some(previous_char)) =
+                    (&state.previous_row, state.previous_char)
+                {
+                    if *query_char == previous_char && self.query[i - 1] == c {
+                        distance = distance.min(previous_row[i - 1] + 1);
+                    }
+                }
+            }
+
+            // Distances above the maximum are all the same to us, so cap them.
+            row.push(distance.min(self.max_distance + 1));
+        }
+
+        LevenshteinState {
+            row,
+            previous_row: Some(state.row.clone()),
+            previous_char: Some(c),
+        }
+    }
+}
+
+/// Returns the terms of the dictionary that start with `prefix` and whose remainder after the
+/// prefix is accepted by the automaton, together with the state the automaton ended in.
+///
+/// Terms are walked in order, reusing the states of the prefix shared with the previous term.
+/// When the automaton cannot match anymore, every term sharing the characters read so far is
+/// skipped by seeking past them in the dictionary.
+pub(crate) fn intersect<'a, A: Automaton, V>(
+    dictionary: &'a BTreeMap<String, V>,
+    prefix: &str,
+    automaton: &A,
+) -> Vec<(&'a str, A::State)> {
+    let mut results = vec![];
+
+    // `states[i]` is the state after reading `i` characters of the previous term's remainder.
+    let mut states = vec![automaton.start()];
+    let mut previous_remainder: Vec<char> = vec![];
+
+    let mut lower_bound: Bound<String> = Bound::Included(prefix.to_string());
+
+    'seek: loop {
+        let terms = dictionary
+            .range::<String, _>((lower_bound.clone(), Bound::Unbounded))
+            .map(|(term, _)| term);
+
+        for term in terms {
+            let remainder = match term.strip_prefix(prefix) {
+                Some(remainder) => remainder,
+                None => break 'seek,
+            };
+            let remainder: Vec<char> = remainder.chars().collect();
+
+            let shared = previous_remainder
+                .iter()
+                .zip(&remainder)
+                .take_while(|(a, b)| a == b)
+                .count();
+            states.truncate(shared + 1);
+
+            for (i, c) in remainder.iter().enumerate().skip(shared) {
+                let state = automaton.step(&states[i], *c);
+
+                if !automaton.can_match(&state) {
+                    // No term starting with these characters can match, so skip all of them.
+                    let dead_prefix: String = remainder[..=i].iter().collect();
+
+                    match successor(&format!("{0}{1}", prefix, dead_prefix)) {
+                        Some(next) => {
+                            lower_bound = Bound::Included(next);
+                            previous_remainder = remainder[..i].to_vec();
+                            continue 'seek;
+                        }
+                        None => break 'seek,
+                    }
+                }
+
+                states.push(state);
+            }
+
+            let state = &states[remainder.len()];
+
+            if automaton.is_match(state) {
+                results.push((term.as_str(), state.clone()));
+            }
+
+            previous_remainder = remainder;
+        }
+
+        break;
+    }
+
+    results
+}
+
+/// Returns the smallest string that is larger than every string starting with `prefix`.
+fn successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+
+    while let Some(last) = chars.pop() {
+        let next = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::automaton::{
+        intersect, successor, Automaton, LevenshteinAutomaton,
+    };
+    use std::collections::BTreeMap;
+
+    fn distance(automaton: &LevenshteinAutomaton, input: &str) -> Option<u32> {
+        let mut state = automaton.start();
+
+        for c in input.chars() {
+            state = automaton.step(&state, c);
+        }
+
+        automaton
+            .is_match(&state)
+            .then(|| automaton.distance(&state))
+    }
+
+    #[test]
+    fn can_accept_within_distance() {
+        let automaton = LevenshteinAutomaton::new("future", 2, false);
+
+        assert_eq!(distance(&automaton, "future"), Some(0));
+        assert_eq!(distance(&automaton, "fuuture"), Some(1));
+        assert_eq!(distance(&automaton, "futur"), Some(1));
+        assert_eq!(distance(&automaton, "fiture"), Some(1));
+        assert_eq!(distance(&automaton, "fuutre"), Some(2));
+        assert_eq!(distance(&automaton, "natures"), None);
+    }
+
+    #[test]
+    fn can_count_transpositions() {
+        let without = LevenshteinAutomaton::new("future", 1, false);
+        let with = LevenshteinAutomaton::new("future", 1, true);
+
+        assert_eq!(distance(&without, "fuuter"), None);
+        assert_eq!(distance(&without, "futuer"), None);
+        assert_eq!(distance(&with, "futuer"), Some(1));
+        assert_eq!(distance(&with, "ufture"), Some(1));
+    }
+
+    #[test]
+    fn can_detect_dead_states() {
+        let automaton = LevenshteinAutomaton::new("cat", 1, false);
+
+        let state = automaton.step(&automaton.start(), 'x');
+        assert!(automaton.can_match(&state));
+
+        let state = automaton.step(&state, 'y');
+        assert!(!automaton.can_match(&state));
+    }
+
+    #[test]
+    fn can_intersect_dictionary() {
+        let dictionary: BTreeMap<String, u32> =
+            ["cat", "cats", "cut", "dog", "xcat", "xylophone", "zebra"]
+                .iter()
+                .enumerate()
+                .map(|(i, term)| (term.to_string(), i as u32))
+                .collect();
+
+        let automaton = LevenshteinAutomaton::new("cat", 1, false);
+
+        let terms: Vec<&str> = intersect(&dictionary, "", &automaton)
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect();
+
+        assert_eq!(terms, vec!["cat", "cats", "cut", "xcat"]);
+
+        // With a prefix, the automaton only runs on the remainder of the terms.
+        let automaton = LevenshteinAutomaton::new("at", 1, false);
+
+        let terms: Vec<&str> = intersect(&dictionary, "c", &automaton)
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect();
+
+        assert_eq!(terms, vec!["cat", "cats", "cut"]);
+    }
+
+    #[test]
+    fn can_find_successor() {
+        assert_eq!(successor("ab"), Some("ac".to_string()));
+        assert_eq!(successor("a\u{10FFFF}"), Some("b".to_string()));
+        assert_eq!(successor("\u{10FFFF}"), None);
+        assert_eq!(successor("\u{D7FF}"), Some("\u{E000}".to_string()));
+    }
+}
@@ -1,20 +1,46 @@
-use crate::rucene_internal::token::Token;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::token::Tokens;
+use std::collections::BTreeMap;
+
+/// Maps the names of stored fields to their original text.
+pub type StoredFields = BTreeMap<String, String>;
 
 #[derive(Debug)]
 pub struct AnalysedDocument {
-    pub id: u32,
-    pub tokens: Vec<Token>,
+    pub id: String,
+
+    // Maps field names to the tokens of the field.
+    pub fields: BTreeMap<String, Tokens>,
+
+    // The original text of the fields that are kept, to be returned with search results.
+    pub stored_fields: StoredFields,
 }
 
 impl AnalysedDocument {
-    pub fn new(id: u32, tokens: Vec<Token>) -> Self {
-        AnalysedDocument { id, tokens }
+    /// A document with only the default field.
+    pub fn new(id: String, tokens: Tokens) -> Self {
+        Self::from_fields(id, BTreeMap::from([(DEFAULT_FIELD.to_string(), tokens)]))
+    }
+
+    pub fn from_fields(id: String, fields: BTreeMap<String, Tokens>) -> Self {
+        AnalysedDocument {
+            id,
+            fields,
+            stored_fields: StoredFields::new(),
+        }
+    }
+
+    pub fn with_stored_fields(mut self, stored_fields: StoredFields) -> Self {
+        self.stored_fields = stored_fields;
+        self
     }
 }
 
 impl PartialEq for AnalysedDocument {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.tokens == other.tokens
+        self.id == other.id
+            && self.fields == other.fields
+            && self.stored_fields == other.stored_fields
     }
 }
 
@@ -22,19 +48,37 @@ impl Eq for AnalysedDocument {}
 
 #[derive(Debug)]
 pub struct DocumentResult {
-    pub id: u32,
+    pub id: String,
+    pub score: f32,
+
+    // The stored fields of the document.
+    pub stored_fields: StoredFields,
 }
 
 impl DocumentResult {
-    pub fn from_id(id: u32) -> Self {
-        DocumentResult { id }
+    pub fn new(id: String, score: f32) -> Self {
+        DocumentResult {
+            id,
+            score,
+            stored_fields: StoredFields::new(),
+        }
+    }
+
+    pub fn from_id(id: String) -> Self {
+        Self::new(id, 0.0)
+    }
+
+    pub fn with_stored_fields(mut self, stored_fields: StoredFields) -> Self {
+        self.stored_fields = stored_fields;
+        self
     }
 
-    pub fn from_ids(ids: Vec<u32>) -> Vec<Self> {
-        ids.iter().map(|id| Self::from_id(*id)).collect()
+    pub fn from_ids(ids: Vec<String>) -> Vec<Self> {
+        ids.into_iter().map(Self::from_id).collect()
     }
 }
 
+/// Results are equal if they refer to the same document, regardless of the score.
 impl PartialEq for DocumentResult {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -0,0 +1,608 @@
+//! A finite state transducer (FST), the structure of Lucene's term dictionary. Like a trie, it
+//! has a transition for each character, but suffixes are shared as well as prefixes, so a large
+//! vocabulary takes a fraction of the memory of its terms. Each term maps to a number, the sum of
+//! the outputs of the transitions on its path.
+//!
+//! The FST is built from terms in ascending order, with the incremental minimization of Daciuk et
+//! al.: once a node can no longer get new transitions it is frozen, and shared with any equal
+//! node that is frozen already. Frozen nodes are written to a byte array, children before their
+//! parents, so that a transition points back to the node it leads to.
+
+use crate::rucene_internal::automaton::Automaton;
+use std::collections::HashMap;
+use std::ops::Bound;
+
+/// A node as it is built and compared for sharing. Transitions are in order of their labels.
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
+struct Node {
+    final_output: Option<u64>,
+    transitions: Vec<Transition>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Transition {
+    label: char,
+    output: u64,
+
+    // The address of the node the transition leads to, unless that node is not frozen yet.
+    target: usize,
+}
+
+/// Maps terms to numbers, with lookups of a term, of the terms in a range, and of the terms that
+/// an automaton accepts. Terms are returned in ascending order.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Fst {
+    bytes: Vec<u8>,
+    root: usize,
+    len: usize,
+}
+
+impl Fst {
+    /// Builds the FST of the terms, which must be in ascending order without duplicates.
+    pub fn from_sorted<'a>(terms: impl IntoIterator<Item = (&'a str, u64)>) -> Self {
+        let mut builder = FstBuilder::new();
+
+        for (term, output) in terms {
+            builder.insert(term, output);
+        }
+
+        builder.finish()
+    }
+
+    /// Reads an FST of `to_bytes`. The bytes should have been checked, since an FST of corrupt
+    /// bytes may panic.
+    pub fn from_bytes(bytes: Vec<u8>, root: usize, len: usize) -> Self {
+        Fst { bytes, root, len }
+    }
+
+    /// Returns the bytes of the nodes, and the address of the root.
+    pub fn to_bytes(&self) -> (&[u8], usize) {
+        (&self.bytes, self.root)
+    }
+
+    /// The number of terms.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn get(&self, term: &str) -> Option<u64> {
+        let mut address = self.root;
+        let mut output = 0;
+
+        for c in term.chars() {
+            let transition = self.transition(address, c)?;
+
+            output += transition.output;
+            address = transition.target;
+        }
+
+        self.node(address)
+            .final_output
+            .map(|final_output| output + final_output)
+    }
+
+    /// Returns the terms within the bounds, with their outputs. Starting at the lower bound is a
+    /// walk down the FST rather than a scan of the terms before it.
+    pub fn range<'a>(
+        &'a self,
+        lower: Bound<&str>,
+        upper: Bound<&'a str>,
+    ) -> impl Iterator<Item = (String, u64)> + 'a {
+        FstStream::new(self, lower).take_while(move |(term, _)| match upper {
+            Bound::Included(upper) => term.as_str() <= upper,
+            Bound::Excluded(upper) => term.as_str() < upper,
+            Bound::Unbounded => true,
+        })
+    }
+
+    /// Returns the terms that start with `prefix` and whose remainder after the prefix is
+    /// accepted by the automaton, together with the state the automaton ended in. Transitions
+    /// after which the automaton can not match anymore are not followed, which skips all terms
+    /// below them at once.
+    pub(crate) fn intersect<A: Automaton>(
+        &self,
+        prefix: &str,
+        automaton: &A,
+    ) -> Vec<(String, A::State)> {
+        let mut address = self.root;
+
+        for c in prefix.chars() {
+            match self.transition(address, c) {
+                Some(transition) => address = transition.target,
+                None => return vec![],
+            }
+        }
+
+        let mut results = vec![];
+        let mut term = prefix.to_string();
+
+        self.intersect_node(
+            address,
+            automaton.start(),
+            automaton,
+            &mut term,
+            &mut results,
+        );
+
+        results
+    }
+
+    fn intersect_node<A: Automaton>(
+        &self,
+        address: usize,
+        state: A::State,
+        automaton: &A,
+        term: &mut String,
+        results: &mut Vec<(String, A::State)>,
+    ) {
+        let node = self.node(address);
+
+        if node.final_output.is_some() && automaton.is_match(&state) {
+            results.push((term.clone(), state.clone()));
+        }
+
+        for transition in node.transitions {
+            let next = automaton.step(&state, transition.label);
+
+            if automaton.can_match(&next) {
+                term.push(transition.label);
+                self.intersect_node(transition.target, next, automaton, term, results);
+                term.pop();
+            }
+        }
+    }
+
+    /// Returns the transition of the node with the label, without decoding the whole node.
+    fn transition(&self, address: usize, label: char) -> Option<Transition> {
+        let mut position = address;
+        let (count, _) = read_node_header(&self.bytes, &mut position);
+
+        for _ in 0..count {
+            let transition = read_transition(&self.bytes, address, &mut position);
+
+            if transition.label >= label {
+                return (transition.label == label).then_some(transition);
+            }
+        }
+
+        None
+    }
+
+    fn node(&self, address: usize) -> Node {
+        let mut position = address;
+        let (count, final_output) = read_node_header(&self.bytes, &mut position);
+
+        Node {
+            final_output,
+            transitions: (0..count)
+                .map(|_| read_transition(&self.bytes, address, &mut position))
+                .collect(),
+        }
+    }
+}
+
+/// Builds an FST from terms in ascending order.
+struct FstBuilder {
+    bytes: Vec<u8>,
+
+    // The address of every frozen node, to share equal nodes.
+    registry: HashMap<Node, usize>,
+
+    // The nodes on the path of the last term, which may still get transitions. The last
+    // transition of each leads to the next one.
+    unfinished: Vec<Node>,
+
+    last_term: Vec<char>,
+    len: usize,
+}
+
+impl FstBuilder {
+    fn new() -> Self {
+        FstBuilder {
+            bytes: vec![],
+            registry: HashMap::new(),
+            unfinished: vec![Node::default()],
+            last_term: vec![],
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, term: &str, mut output: u64) {
+        let term: Vec<char> = term.chars().collect();
+        debug_assert!(self.len == 0 || term > self.last_term);
+
+        let prefix_len = term
+            .iter()
+            .zip(&self.last_term)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        // Nodes past the shared prefix are done, since later terms are larger.
+        self.freeze_from(prefix_len);
+
+        // The shared transitions keep the part of their output that this term has too, and push
+        // the rest further down, to the transitions of the terms before.
+        for depth in 0..prefix_len {
+            let transition = self.unfinished[depth].transitions.last_mut().unwrap();
+            let shared = transition.output.min(output);
+            let rest = transition.output - shared;
+
+            transition.output = shared;
+            output -= shared;
+
+            if rest > 0 {
+                let next = &mut self.unfinished[depth + 1];
+
+                for transition in &mut next.transitions {
+                    transition.output += rest;
+                }
+                if let Some(final_output) = &mut next.final_output {
+                    *final_output += rest;
+                }
+            }
+        }
+
+        for label in &term[prefix_len..] {
+            self.unfinished
+                .last_mut()
+                .unwrap()
+                .transitions
+                .push(Transition {
+                    label: *label,
+                    output,
+                    target: 0,
+                });
+            self.unfinished.push(Node::default());
+            output = 0;
+        }
+
+        self.unfinished.last_mut().unwrap().final_output = Some(output);
+
+        self.last_term = term;
+        self.len += 1;
+    }
+
+    /// Freezes the unfinished nodes deeper than `depth`.
+    fn freeze_from(&mut self, depth: usize) {
+        while self.unfinished.len() > depth + 1 {
+            let node = self.unfinished.pop().unwrap();
+            let address = self.freeze(node);
+
+            let parent = self.unfinished.last_mut().unwrap();
+            parent.transitions.last_mut().unwrap().target = address;
+        }
+    }
+
+    /// Writes the node, unless an equal node has been written already, and returns its address.
+    fn freeze(&mut self, node: Node) -> usize {
+        if let Some(address) = self.registry.get(&node) {
+            return *address;
+        }
+
+        let address = self.bytes.len();
+        write_node(&mut self.bytes, address, &node);
+        self.registry.insert(node, address);
+
+        address
+    }
+
+    fn finish(mut self) -> Fst {
+        self.freeze_from(0);
+
+        let root = self.unfinished.pop().unwrap();
+        let root = self.freeze(root);
+        self.bytes.shrink_to_fit();
+
+        Fst {
+            bytes: self.bytes,
+            root,
+            len: self.len,
+        }
+    }
+}
+
+/// A node is the number of its transitions with a bit for whether it is final, the final output
+/// if it is, and its transitions: the label, the output and how far back the target is.
+fn write_node(bytes: &mut Vec<u8>, address: usize, node: &Node) {
+    let count = node.transitions.len() as u64;
+    write_vint(bytes, count << 1 | node.final_output.is_some() as u64);
+
+    if let Some(final_output) = node.final_output {
+        write_vint(bytes, final_output);
+    }
+
+    for transition in &node.transitions {
+        write_vint(bytes, transition.label as u64);
+        write_vint(bytes, transition.output);
+        write_vint(bytes, (address - transition.target) as u64);
+    }
+}
+
+fn read_node_header(bytes: &[u8], position: &mut usize) -> (usize, Option<u64>) {
+    let header = read_vint(bytes, position);
+    let final_output = (header & 1 == 1).then(|| read_vint(bytes, position));
+
+    ((header >> 1) as usize, final_output)
+}
+
+fn read_transition(bytes: &[u8], address: usize, position: &mut usize) -> Transition {
+    let label = char::from_u32(read_vint(bytes, position) as u32).unwrap_or_default();
+    let output = read_vint(bytes, position);
+    let target = address - read_vint(bytes, position) as usize;
+
+    Transition {
+        label,
+        output,
+        target,
+    }
+}
+
+// Numbers take seven bits per byte, like in `store`.
+fn write_vint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+fn read_vint(bytes: &[u8], position: &mut usize) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = bytes[*position];
+        *position += 1;
+
+        value |= ((byte & 0x7f) as u64) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return value;
+        }
+    }
+}
+
+/// Walks the terms of an FST in order, depth first.
+struct FstStream<'a> {
+    fst: &'a Fst,
+
+    // The nodes on the path to the current term, with the output so far and the next
+    // transition to follow.
+    stack: Vec<(Node, u64, usize)>,
+
+    term: String,
+
+    // A term found while seeking the lower bound, which comes before the rest.
+    pending: Option<(String, u64)>,
+}
+
+impl<'a> FstStream<'a> {
+    /// Starts at the first term within the lower bound, by following its characters down.
+    fn new(fst: &'a Fst, lower: Bound<&str>) -> Self {
+        let (bound, inclusive) = match lower {
+            Bound::Included(bound) => (bound, true),
+            Bound::Excluded(bound) => (bound, false),
+            Bound::Unbounded => ("", true),
+        };
+
+        let mut stream = FstStream {
+            fst,
+            stack: vec![(fst.node(fst.root), 0, 0)],
+            term: String::new(),
+            pending: None,
+        };
+
+        let mut chars = bound.chars();
+
+        loop {
+            let (node, output, next) = stream.stack.last_mut().unwrap();
+
+            let Some(c) = chars.next() else {
+                // Every term below the node is larger than the bound, and the node is the bound.
+                if let (true, Some(final_output)) = (inclusive, node.final_output) {
+                    stream.pending = Some((stream.term.clone(), *output + final_output));
+                }
+                break;
+            };
+
+            *next = node
+                .transitions
+                .iter()
+                .position(|transition| transition.label >= c)
+                .unwrap_or(node.transitions.len());
+
+            match node.transitions.get(*next) {
+                Some(transition) if transition.label == c => {
+                    let transition = *transition;
+                    let output = *output + transition.output;
+                    *next += 1;
+
+                    stream.term.push(c);
+                    stream.stack.push((fst.node(transition.target), output, 0));
+                }
+                _ => break,
+            }
+        }
+
+        stream
+    }
+}
+
+impl Iterator for FstStream<'_> {
+    type Item = (String, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(pending) = self.pending.take() {
+            return Some(pending);
+        }
+
+        loop {
+            let (node, output, next) = self.stack.last_mut()?;
+
+            let Some(transition) = node.transitions.get(*next).copied() else {
+                self.stack.pop();
+                self.term.pop();
+                continue;
+            };
+
+            *next += 1;
+
+            let output = *output + transition.output;
+            let target = self.fst.node(transition.target);
+            let found = target
+                .final_output
+                .map(|final_output| output + final_output);
+
+            self.term.push(transition.label);
+            self.stack.push((target, output, 0));
+
+            if let Some(found) = found {
+                return Some((self.term.clone(), found));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rucene_internal::automaton::LevenshteinAutomaton;
+    use std::collections::BTreeMap;
+
+    fn init_terms() -> BTreeMap<String, u64> {
+        [
+            "cat",
+            "cats",
+            "cut",
+            "dog",
+            "dogs",
+            "xcat",
+            "xylophone",
+            "zebra",
+            "zoë",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, term)| (term.to_string(), (i as u64 * 7) % 5))
+        .collect()
+    }
+
+    fn init_fst(terms: &BTreeMap<String, u64>) -> Fst {
+        Fst::from_sorted(terms.iter().map(|(term, output)| (term.as_str(), *output)))
+    }
+
+    fn range(fst: &Fst, lower: Bound<&str>, upper: Bound<&str>) -> Vec<String> {
+        fst.range(lower, upper).map(|(term, _)| term).collect()
+    }
+
+    #[test]
+    fn can_get_terms() {
+        let terms = init_terms();
+        let fst = init_fst(&terms);
+
+        assert_eq!(fst.len(), terms.len());
+
+        for (term, output) in &terms {
+            assert_eq!(fst.get(term), Some(*output), "{0}", term);
+        }
+
+        for term in ["", "c", "ca", "catss", "do", "zo", "zebras"] {
+            assert_eq!(fst.get(term), None, "{0}", term);
+        }
+    }
+
+    #[test]
+    fn can_get_empty_term() {
+        let fst = Fst::from_sorted([("", 3), ("a", 1)]);
+
+        assert_eq!(fst.get(""), Some(3));
+        assert_eq!(
+            range(&fst, Bound::Unbounded, Bound::Unbounded),
+            vec!["", "a"]
+        );
+
+        let fst = Fst::from_sorted([]);
+
+        assert_eq!(fst.get(""), None);
+        assert!(range(&fst, Bound::Unbounded, Bound::Unbounded).is_empty());
+    }
+
+    #[test]
+    fn can_iterate_terms_in_order() {
+        let terms = init_terms();
+        let fst = init_fst(&terms);
+
+        let iterated: Vec<(String, u64)> = fst.range(Bound::Unbounded, Bound::Unbounded).collect();
+
+        assert_eq!(iterated, terms.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn can_get_range() {
+        let fst = init_fst(&init_terms());
+
+        assert_eq!(
+            range(&fst, Bound::Included("cats"), Bound::Excluded("dogs")),
+            vec!["cats", "cut", "dog"]
+        );
+        assert_eq!(
+            range(&fst, Bound::Excluded("cats"), Bound::Included("dogs")),
+            vec!["cut", "dog", "dogs"]
+        );
+        assert_eq!(
+            range(&fst, Bound::Included("ca"), Bound::Included("cu")),
+            vec!["cat", "cats"]
+        );
+        assert_eq!(
+            range(&fst, Bound::Included("y"), Bound::Unbounded),
+            vec!["zebra", "zoë"]
+        );
+        assert!(range(&fst, Bound::Included("zz"), Bound::Unbounded).is_empty());
+        assert!(range(&fst, Bound::Included("dog"), Bound::Excluded("cat")).is_empty());
+    }
+
+    #[test]
+    fn can_intersect_automaton() {
+        let fst = init_fst(&init_terms());
+
+        let automaton = LevenshteinAutomaton::new("cat", 1, false);
+        let terms: Vec<String> = fst
+            .intersect("", &automaton)
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect();
+
+        assert_eq!(terms, vec!["cat", "cats", "cut", "xcat"]);
+
+        // With a prefix, the automaton only runs on the remainder of the terms.
+        let automaton = LevenshteinAutomaton::new("at", 1, false);
+        let terms: Vec<String> = fst
+            .intersect("c", &automaton)
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect();
+
+        assert_eq!(terms, vec!["cat", "cats", "cut"]);
+        assert!(fst.intersect("q", &automaton).is_empty());
+    }
+
+    #[test]
+    fn shares_suffixes() {
+        let fst = Fst::from_sorted([("bats", 0), ("cats", 0), ("rats", 0)]);
+        let shared = Fst::from_sorted([("bats", 0)]);
+
+        // The root has three transitions to the same "ats" path.
+        assert!(fst.size_in_bytes() < shared.size_in_bytes() + 10);
+        assert_eq!(fst.get("rats"), Some(0));
+    }
+}
@@ -1,131 +1,598 @@
-use crate::rucene_internal::token::Tokens;
-use crate::rucene_internal::utils::filter_vector;
+use crate::rucene_internal::document::{AnalysedDocument, StoredFields};
+use crate::rucene_internal::live_docs::LiveDocs;
+use crate::rucene_internal::merge_policy::{MergePolicy, SegmentInfo, TieredMergePolicy};
+use crate::rucene_internal::segment::{Segment, SegmentReader};
+use crate::rucene_internal::similarity::{BM25Similarity, Similarity};
+use crate::rucene_internal::store::{DataInput, DataOutput, Directory, COMMIT_FILE};
 use crate::{DocumentResult, Query};
-use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::error::Error;
+use std::iter;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
 
-// These are BTree's rather than HashMap's, since HashMaps are not ordered.
-type TermDictionary = BTreeMap<String, u32>;
-type PostingsList = BTreeMap<u32, Vec<u32>>;
+// The commit file lists the segments of the index, and their live documents.
+const COMMIT_CODEC: &str = "RuceneCommit";
+const COMMIT_VERSION: u32 = 2;
 
-/// `InvertedIndex` is the heart of Rucene.
+// New documents are flushed to a segment once there are this many.
+const DEFAULT_MAX_BUFFERED_DOCS: usize = 1000;
+
+/// A segment and which of its documents are live. Segments never change once they are flushed,
+/// so they are shared with the merges that run in the background; only the live documents change.
+#[derive(Default, Clone)]
+struct SegmentState {
+    segment: Arc<Segment>,
+    live_docs: LiveDocs,
+
+    // The estimated size of the segment, computed once since the segment does not change.
+    size_in_bytes: u64,
+}
+
+impl SegmentState {
+    fn new(segment: Segment, live_docs: LiveDocs) -> Self {
+        SegmentState {
+            size_in_bytes: segment.size_in_bytes(),
+            segment: Arc::new(segment),
+            live_docs,
+        }
+    }
+
+    fn info(&self) -> SegmentInfo {
+        SegmentInfo {
+            size_in_bytes: self.size_in_bytes,
+            max_doc: self.segment.max_doc(),
+            deleted_docs: self.segment.max_doc() - self.live_docs.len() as u32,
+        }
+    }
+}
+
+/// A merge that finished in the background, and still has to replace the segments it merged.
+struct FinishedMerge {
+    segment: Segment,
+
+    // The names of the merged segments.
+    merged: Vec<String>,
+
+    // For each merged segment, the new number of each of its documents.
+    doc_maps: Vec<Vec<Option<u32>>>,
+}
+
+/// `InvertedIndex` is the heart of Rucene. Like in Lucene, it consists of immutable segments, each
+/// with its own term dictionaries and postings. New documents are buffered in memory, and flushed
+/// to a new segment once the buffer is full. Searches run on every segment, and the merge policy
+/// decides which segments are merged together in the background, which also drops the documents
+/// that are deleted.
 pub(crate) struct InvertedIndex {
-    id_acc: u32,
+    // The flushed segments, oldest first.
+    segments: Vec<SegmentState>,
+
+    // The new documents that have not been flushed yet. Snapshots flush them first, so the
+    // buffer is never shared.
+    buffer: SegmentState,
+
+    max_buffered_docs: usize,
+
+    // The number of the next segment, which names its files.
+    segment_acc: u32,
 
-    // Maps terms to ordinal numbers that uniquely identify a term.
-    term_dictionary: TermDictionary,
+    // The sequence number of the last translog operation that the last commit contains.
+    committed_seq_no: u64,
 
-    // Maps term ID to ID's of documents that contain the term.
-    postings_list: PostingsList,
+    // Scores the documents that match a query. Snapshots share it.
+    similarity: Arc<dyn Similarity>,
+
+    merge_policy: Box<dyn MergePolicy>,
+
+    // The names of the segments that are being merged in the background.
+    merging: BTreeSet<String>,
+
+    // Merges send their result when they finish, and it is installed on the next change.
+    merge_sender: Sender<FinishedMerge>,
+    merge_receiver: Receiver<FinishedMerge>,
 }
 
 impl InvertedIndex {
     pub fn new() -> Self {
+        let (merge_sender, merge_receiver) = mpsc::channel();
+
         Self {
-            id_acc: 0,
-            term_dictionary: TermDictionary::new(),
-            postings_list: PostingsList::new(),
+            segments: vec![],
+            buffer: SegmentState::default(),
+            max_buffered_docs: DEFAULT_MAX_BUFFERED_DOCS,
+            segment_acc: 0,
+            committed_seq_no: 0,
+            similarity: Arc::new(BM25Similarity::default()),
+            merge_policy: Box::new(TieredMergePolicy::default()),
+            merging: BTreeSet::new(),
+            merge_sender,
+            merge_receiver,
         }
     }
 
-    /// Index the document in the `InvertedIndex`.
-    pub(crate) fn index(
+    pub fn with_similarity(similarity: Box<dyn Similarity>) -> Self {
+        Self {
+            similarity: Arc::from(similarity),
+            ..Self::new()
+        }
+    }
+
+    pub(crate) fn set_merge_policy(&mut self, merge_policy: Box<dyn MergePolicy>) {
+        self.merge_policy = merge_policy;
+    }
+
+    /// Sets how many new documents are buffered in memory before they are flushed to a segment.
+    pub(crate) fn set_max_buffered_docs(&mut self, max_buffered_docs: usize) {
+        self.max_buffered_docs = max_buffered_docs.max(1);
+    }
+
+    /// Flushes the buffer, and writes the index to the directory as a new commit, which contains
+    /// the translog operations up to `seq_no`. Only segments that are new since the last commit
+    /// are written.
+    pub(crate) fn commit(
         &mut self,
-        document: super::document::AnalysedDocument,
+        directory: &Directory,
+        seq_no: u64,
     ) -> Result<(), Box<dyn Error>> {
-        for token in document.tokens {
-            match self.term_dictionary.get(&token.value) {
-                Some(val) => {
-                    // Term exists in the dictionary; a document containing the term has been indexed before.
-                    self.postings_list
-                        .get_mut(val)
-                        .ok_or_else(|| {
-                            format!(
-                                // If a term exist in the term dictionary, then it should exist in
-                                // the posting list as well, since we add it to the posting list when created.
-                                "Value {0} is in the term dictionary but not in the posting list.",
-                                val
-                            )
-                        })?
-                        .push(document.id);
+        self.apply_finished_merges();
+        self.flush();
+
+        let mut output = DataOutput::new(COMMIT_CODEC, COMMIT_VERSION);
+        let mut files = vec![];
+
+        output.write_vint(self.segment_acc as u64);
+        output.write_vint(seq_no);
+        output.write_vint(self.segments.len() as u64);
+
+        for state in &self.segments {
+            state.segment.write(directory)?;
+            files.extend(state.segment.files());
+
+            output.write_string(&state.segment.name);
+            output.write_vint(state.live_docs.words().len() as u64);
+            for word in state.live_docs.words() {
+                output.write_u64(*word);
+            }
+        }
+
+        directory.commit(&output.finish(), &files)?;
+        self.committed_seq_no = seq_no;
+
+        Ok(())
+    }
+
+    pub(crate) fn committed_seq_no(&self) -> u64 {
+        self.committed_seq_no
+    }
+
+    /// Reads the index of the directory's last commit, or an empty index if there is none.
+    pub(crate) fn open(
+        directory: &Directory,
+        similarity: Box<dyn Similarity>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut index = Self::with_similarity(similarity);
+
+        let Some(bytes) = directory.read_commit()? else {
+            return Ok(index);
+        };
+
+        let mut input = DataInput::new(COMMIT_FILE, &bytes, COMMIT_CODEC, COMMIT_VERSION)?;
+        let mut files = vec![];
+
+        index.segment_acc = input.read_vint_u32()?;
+        index.committed_seq_no = input.read_vint()?;
+
+        for _ in 0..input.read_vint()? {
+            let name = input.read_string()?;
+
+            let mut words = vec![];
+            for _ in 0..input.read_vint()? {
+                words.push(input.read_u64()?);
+            }
+
+            let segment = Segment::read(directory, &name)?;
+            files.extend(segment.files());
+
+            index
+                .segments
+                .push(SegmentState::new(segment, LiveDocs::from_words(words)));
+        }
+
+        input.finish()?;
+
+        // A commit that failed halfway may have left files behind, under names that new segments
+        // will use again.
+        directory.remove_unreferenced(&files)?;
+
+        Ok(index)
+    }
+
+    /// Index the document in the `InvertedIndex`. A document ID that is indexed again replaces the
+    /// previous version: it is deleted, and the new version is added as a new document.
+    pub(crate) fn index(&mut self, document: AnalysedDocument) -> Result<(), Box<dyn Error>> {
+        self.apply_finished_merges();
+        self.delete_doc(&document.id);
+
+        let doc = Arc::make_mut(&mut self.buffer.segment).add(document)?;
+        self.buffer.live_docs.set(doc);
+
+        if self.buffer.segment.max_doc() as usize >= self.max_buffered_docs {
+            self.flush();
+        }
+
+        Ok(())
+    }
+
+    /// Turns the buffer into a new segment, unless all of its documents have been deleted.
+    fn flush(&mut self) {
+        let mut state = std::mem::take(&mut self.buffer);
+
+        if state.live_docs.len() == 0 {
+            return;
+        }
+
+        let segment = Arc::make_mut(&mut state.segment);
+        segment.name = self.next_segment_name();
+        segment.finish();
+
+        state.size_in_bytes = state.segment.size_in_bytes();
+
+        self.segments.push(state);
+        self.maybe_merge();
+    }
+
+    fn next_segment_name(&mut self) -> String {
+        self.segment_acc += 1;
+
+        format!("_{0}", self.segment_acc - 1)
+    }
+
+    /// Starts the merges that the merge policy finds, among the segments that are not being merged
+    /// yet.
+    fn maybe_merge(&mut self) {
+        let candidates: Vec<&SegmentState> = self
+            .segments
+            .iter()
+            .filter(|state| !self.merging.contains(&state.segment.name))
+            .collect();
+        let infos: Vec<SegmentInfo> = candidates.iter().map(|state| state.info()).collect();
+
+        let merges: Vec<Vec<SegmentState>> = self
+            .merge_policy
+            .find_merges(&infos)
+            .into_iter()
+            .map(|mut merge| {
+                // Merged documents keep the order they were added in, which breaks ties in
+                // rankings.
+                merge.sort();
+                merge.iter().map(|i| candidates[*i].clone()).collect()
+            })
+            .collect();
+
+        for merge in merges {
+            self.start_merge(merge);
+        }
+    }
+
+    /// Merges the segments on a thread of its own. Searches and changes go on meanwhile, on the
+    /// segments themselves.
+    fn start_merge(&mut self, states: Vec<SegmentState>) {
+        let name = self.next_segment_name();
+        let sender = self.merge_sender.clone();
+
+        for state in &states {
+            self.merging.insert(state.segment.name.clone());
+        }
+
+        thread::spawn(move || {
+            let segments: Vec<(&Segment, &LiveDocs)> = states
+                .iter()
+                .map(|state| (state.segment.as_ref(), &state.live_docs))
+                .collect();
+            let (segment, doc_maps) = Segment::merge(name, &segments);
+
+            // The index may be gone by now, and then so is the need for the merge.
+            let _ = sender.send(FinishedMerge {
+                segment,
+                merged: states
+                    .iter()
+                    .map(|state| state.segment.name.clone())
+                    .collect(),
+                doc_maps,
+            });
+        });
+    }
+
+    /// Replaces the merged segments of the merges that finished with the new segments.
+    fn apply_finished_merges(&mut self) {
+        let mut applied = false;
+
+        while let Ok(merge) = self.merge_receiver.try_recv() {
+            self.apply_merge(merge);
+            applied = true;
+        }
+
+        if applied {
+            self.maybe_merge();
+        }
+    }
+
+    fn apply_merge(&mut self, merge: FinishedMerge) {
+        let mut live_docs = LiveDocs::all(merge.segment.max_doc());
+
+        // Documents that were deleted while the merge ran are still in the merged segment.
+        for (name, doc_map) in merge.merged.iter().zip(&merge.doc_maps) {
+            self.merging.remove(name);
+
+            let Some(state) = self
+                .segments
+                .iter()
+                .find(|state| &state.segment.name == name)
+            else {
+                continue;
+            };
+
+            for (doc, new_doc) in doc_map.iter().enumerate() {
+                if let Some(new_doc) = new_doc {
+                    if !state.live_docs.get(doc as u32) {
+                        live_docs.clear(*new_doc);
+                    }
                 }
-                None => {
-                    // Term does not exist in the dictionary; a document containing the term has not
-                    // been indexed before.
-                    self.term_dictionary
-                        .insert(token.value.clone(), self.id_acc);
-
-                    self.postings_list.insert(self.id_acc, vec![document.id]);
-                    self.id_acc += 1;
+            }
+        }
+
+        // The merged segment takes the place of the oldest segment it merged.
+        let position = self
+            .segments
+            .iter()
+            .position(|state| merge.merged.contains(&state.segment.name))
+            .unwrap_or(self.segments.len());
+
+        self.segments
+            .retain(|state| !merge.merged.contains(&state.segment.name));
+
+        if live_docs.len() > 0 {
+            self.segments
+                .insert(position, SegmentState::new(merge.segment, live_docs));
+        }
+    }
+
+    /// Waits until the merges that run in the background have finished, including the merges they
+    /// lead to.
+    pub(crate) fn wait_for_merges(&mut self) {
+        while !self.merging.is_empty() {
+            match self.merge_receiver.recv() {
+                Ok(merge) => {
+                    self.apply_merge(merge);
+                    self.maybe_merge();
                 }
-            };
+                Err(_) => break,
+            }
         }
+    }
 
-        Ok(())
+    /// Returns the buffer after the flushed segments, in the order that documents were added.
+    fn states(&self) -> impl DoubleEndedIterator<Item = &SegmentState> {
+        self.segments.iter().chain(iter::once(&self.buffer))
     }
 
-    /// Retrieve the list of document in the `InvertedIndex`, based on a `Query`.
+    /// Returns a snapshot of the index as it is now, which later changes do not affect. Like
+    /// Lucene's near-real-time readers, the snapshot flushes the buffer to a small segment, which
+    /// the merge policy later merges with others, so that new documents never change a segment
+    /// that a snapshot shares.
+    pub(crate) fn snapshot(&mut self) -> Snapshot {
+        self.apply_finished_merges();
+        self.flush();
+
+        Snapshot {
+            segments: self.states().cloned().collect(),
+            similarity: self.similarity.clone(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Retrieve the list of document in the `InvertedIndex`, based on a `Query`, including the
+    /// documents that have not been flushed yet.
+    #[cfg(test)]
     pub(crate) fn retrieve(&self, query: Query) -> Result<Vec<DocumentResult>, Box<dyn Error>> {
-        let must_results = self.get_ids_from_tokens(query.must)?;
-        let must_not_results = self.get_ids_from_tokens(query.must_not)?;
+        let snapshot = Snapshot {
+            segments: self.states().cloned().collect(),
+            similarity: self.similarity.clone(),
+        };
 
-        let filtered_results = filter_vector(must_results, must_not_results);
+        snapshot.retrieve(query)
+    }
+
+    /// Returns the stored fields of the document, if it has been indexed and not deleted. Unlike
+    /// a snapshot, this includes the latest changes.
+    pub(crate) fn stored_fields(&self, id: &str) -> Option<&StoredFields> {
+        live_doc(self.states(), id).map(|(state, doc)| state.segment.stored_fields(doc))
+    }
+
+    /// Deletes the document, so that queries no longer return it. Its postings are only dropped
+    /// when its segment is merged. Returns whether the document was live.
+    pub(crate) fn delete(&mut self, id: &str) -> bool {
+        self.apply_finished_merges();
+
+        let deleted = self.delete_doc(id);
+
+        // Segments with many deletes may be worth merging now.
+        if deleted {
+            self.maybe_merge();
+        }
+
+        deleted
+    }
 
-        Ok(DocumentResult::from_ids(filtered_results))
+    fn delete_doc(&mut self, id: &str) -> bool {
+        self.segments
+            .iter_mut()
+            .chain(iter::once(&mut self.buffer))
+            .rev()
+            .any(|state| match state.segment.doc_number(id) {
+                Some(doc) => state.live_docs.clear(doc),
+                None => false,
+            })
     }
 
+    /// Merges all segments, including the buffer, into a single segment without deleted
+    /// documents. Until then, deleted documents still count in the term and collection statistics,
+    /// like in Lucene.
+    pub(crate) fn compact(&mut self) {
+        self.wait_for_merges();
+        self.flush();
+        self.wait_for_merges();
+
+        let deleted_docs: u32 = self
+            .segments
+            .iter()
+            .map(|state| state.info().deleted_docs)
+            .sum();
+
+        if self.segments.len() <= 1 && deleted_docs == 0 {
+            return;
+        }
+
+        let name = self.next_segment_name();
+        let states = std::mem::take(&mut self.segments);
+        let segments: Vec<(&Segment, &LiveDocs)> = states
+            .iter()
+            .map(|state| (state.segment.as_ref(), &state.live_docs))
+            .collect();
+
+        let (segment, _) = Segment::merge(name, &segments);
+
+        if segment.max_doc() > 0 {
+            let live_docs = LiveDocs::all(segment.max_doc());
+
+            self.segments.push(SegmentState::new(segment, live_docs));
+        }
+    }
+
+    /// Returns the sorted numbers of documents of the buffer that contain the term in the field.
     #[cfg(test)]
-    fn get_documents_from_tokens(
+    fn get_docs_from_term(&self, field: &str, term: &str) -> Result<Vec<u32>, Box<dyn Error>> {
+        self.buffer.segment.get_docs_from_term(field, term)
+    }
+
+    #[cfg(test)]
+    fn get_postings(
         &self,
-        tokens: Tokens,
-    ) -> Result<Vec<DocumentResult>, Box<dyn Error>> {
-        let doc_ids: Vec<u32> = self.get_ids_from_tokens(tokens)?;
-
-        Ok(DocumentResult::from_ids(doc_ids))
-    }
-
-    fn get_ids_from_tokens(&self, tokens: Tokens) -> Result<Vec<u32>, Box<dyn Error>> {
-        let mut doc_ids: Vec<u32> = vec![];
-
-        for token in tokens {
-            if let Some(id) = self.term_dictionary.get(token.value.as_str()) {
-                let document_ids = self.postings_list.get(id).ok_or_else(|| {
-                    format!(
-                        // If a term exist in the term dictionary, then it should exist in
-                        // the posting list as well, since we add it to the posting list when created.
-                        "Value {0} is in the term dictionary but not in the posting list.",
-                        id
-                    )
-                })?;
-
-                // Remove duplicate document IDs.
-                document_ids.iter().for_each(|id| {
-                    if !doc_ids.contains(id) {
-                        doc_ids.push(*id);
-                    }
-                });
-            } else {
-                // If the term does not exist in the dictionary, then it means that no document containing the term has been indexed.
-                break;
-            }
+        field: &str,
+        term: &str,
+    ) -> Result<&[crate::rucene_internal::postings::Posting], Box<dyn Error>> {
+        self.buffer.segment.get_postings(field, term)
+    }
+
+    /// Returns a reader of the buffer, which holds the documents of tests that index fewer
+    /// documents than fit in it.
+    #[cfg(test)]
+    pub(crate) fn reader(&self) -> SegmentReader<'_> {
+        SegmentReader::new(
+            &self.buffer.segment,
+            &[self.buffer.segment.as_ref()],
+            self.similarity.as_ref(),
+        )
+    }
+}
+
+/// The segments of the index at a point in time. Snapshots share the segments with the index and
+/// with each other, so they are cheap to take, and searching them needs no lock on the index.
+pub(crate) struct Snapshot {
+    segments: Vec<SegmentState>,
+    similarity: Arc<dyn Similarity>,
+}
+
+impl Snapshot {
+    /// Retrieve the list of document in the `Snapshot`, based on a `Query`.
+    pub(crate) fn retrieve(&self, query: Query) -> Result<Vec<DocumentResult>, Box<dyn Error>> {
+        let segments: Vec<&Segment> = self
+            .segments
+            .iter()
+            .map(|state| state.segment.as_ref())
+            .collect();
+        let mut results: Vec<(&SegmentState, u32, f32)> = vec![];
+
+        for state in &self.segments {
+            let reader = SegmentReader::new(&state.segment, &segments, self.similarity.as_ref());
+            let scores = query.matches(&reader)?;
+
+            results.extend(
+                scores
+                    .into_iter()
+                    .filter(|(doc, _)| state.live_docs.get(*doc))
+                    .map(|(doc, score)| (state, doc, score)),
+            );
         }
 
-        Ok(doc_ids)
+        // Higher scores come first. The sort is stable, so ties stay in the order of the
+        // segments and documents.
+        results.sort_by(|(_, _, a), (_, _, b)| b.total_cmp(a));
+
+        Ok(results
+            .into_iter()
+            .map(|(state, doc, score)| {
+                let id = state.segment.id(doc).to_string();
+                let stored_fields = state.segment.stored_fields(doc).clone();
+
+                DocumentResult::new(id, score).with_stored_fields(stored_fields)
+            })
+            .collect())
+    }
+
+    /// Returns the stored fields of the document, if it was indexed and not deleted at the time
+    /// of the snapshot.
+    pub(crate) fn stored_fields(&self, id: &str) -> Option<&StoredFields> {
+        live_doc(self.segments.iter(), id).map(|(state, doc)| state.segment.stored_fields(doc))
+    }
+
+    /// The number of documents that are live.
+    pub(crate) fn num_docs(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|state| state.live_docs.len())
+            .sum()
     }
 }
 
+/// Returns the segment and number of the document, if it has been indexed and not deleted. Older
+/// versions of a document are deleted, so there is at most one.
+fn live_doc<'a>(
+    states: impl DoubleEndedIterator<Item = &'a SegmentState>,
+    id: &str,
+) -> Option<(&'a SegmentState, u32)> {
+    states.rev().find_map(|state| {
+        state
+            .segment
+            .doc_number(id)
+            .filter(|doc| state.live_docs.get(*doc))
+            .map(|doc| (state, doc))
+    })
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::rucene_internal::merge_policy::{LogByteSizeMergePolicy, NoMergePolicy};
+    use crate::rucene_internal::query::{BooleanQuery, TermQuery};
+    use crate::rucene_internal::schema::DEFAULT_FIELD;
+    use crate::rucene_internal::similarity::BooleanSimilarity;
+    use crate::rucene_internal::store::tests::temp_dir;
     use crate::rucene_internal::token::Token;
-    use crate::AnalysedDocument;
+    use std::collections::BTreeMap;
+    use std::fs;
 
-    fn init_test_inv_index() -> InvertedIndex {
+    pub(crate) fn init_test_inv_index() -> InvertedIndex {
         let mut inv_index = InvertedIndex::new();
 
         let documents = vec![
             AnalysedDocument::new(
-                0,
+                "0".to_string(),
                 vec![
                     Token::new(String::from("back")),
                     Token::new(String::from("to")),
@@ -134,14 +601,14 @@ mod tests {
                 ],
             ),
             AnalysedDocument::new(
-                1,
+                "1".to_string(),
                 vec![
                     Token::new(String::from("future")),
                     Token::new(String::from("cop")),
                 ],
             ),
             AnalysedDocument::new(
-                2,
+                "2".to_string(),
                 vec![
                     Token::new(String::from("back")),
                     Token::new(String::from("again")),
@@ -158,81 +625,405 @@ mod tests {
         inv_index
     }
 
-    #[test]
-    fn can_index_term_dictionary() {
-        let inv_index = init_test_inv_index();
+    /// Returns the segment of `init_test_inv_index`, which has not been flushed.
+    pub(crate) fn init_test_segment() -> Segment {
+        init_test_inv_index().buffer.segment.as_ref().clone()
+    }
 
-        let expected_term_dict = BTreeMap::from([
-            (String::from("again"), 5),
-            (String::from("back"), 0),
-            (String::from("cop"), 4),
-            (String::from("future"), 3),
-            (String::from("the"), 2),
-            (String::from("to"), 1),
-        ]);
+    /// Indexes each text as a document, with whitespace separated tokens and the position in
+    /// `texts` as document ID.
+    pub(crate) fn init_inv_index_from_texts(texts: &[&str]) -> InvertedIndex {
+        let mut inv_index = InvertedIndex::new();
+
+        for (id, text) in texts.iter().enumerate() {
+            let tokens = text
+                .split_whitespace()
+                .map(|word| Token::new(word.to_string()))
+                .collect();
+
+            inv_index
+                .index(AnalysedDocument::new(id.to_string(), tokens))
+                .unwrap();
+        }
 
-        assert_eq!(expected_term_dict, inv_index.term_dictionary);
+        inv_index
     }
 
     #[test]
-    fn can_index_postings_list() {
-        let inv_index = init_test_inv_index();
+    fn can_retrieve_stored_fields() {
+        let mut inv_index = InvertedIndex::new();
+
+        let document = |id: &str, title: &str| {
+            AnalysedDocument::new(id.to_string(), vec![Token::new("future".to_string())])
+                .with_stored_fields(StoredFields::from([(
+                    "title".to_string(),
+                    title.to_string(),
+                )]))
+        };
+
+        inv_index
+            .index(document("a", "Back to the Future"))
+            .unwrap();
+        inv_index.index(document("b", "Future Cop")).unwrap();
+        inv_index.index(document("b", "Future Cop 2")).unwrap();
+
+        let results = inv_index
+            .retrieve(TermQuery::new("future".to_string()).into())
+            .unwrap();
 
-        let expected_posting_list = BTreeMap::from([
-            (5, vec![2]),
-            (0, vec![0, 2]),
-            (4, vec![1]),
-            (3, vec![0, 1]),
-            (2, vec![0]),
-            (1, vec![0]),
-        ]);
+        let titles: BTreeMap<&str, &str> = results
+            .iter()
+            .map(|result| (result.id.as_str(), result.stored_fields["title"].as_str()))
+            .collect();
 
-        assert_eq!(expected_posting_list, inv_index.postings_list);
+        assert_eq!(
+            titles,
+            BTreeMap::from([("a", "Back to the Future"), ("b", "Future Cop 2")])
+        );
+        assert!(inv_index.stored_fields("c").is_none());
     }
 
     #[test]
-    fn can_get_must_documents() {
-        let inv_index = init_test_inv_index();
+    fn can_number_documents() {
+        let mut inv_index = InvertedIndex::new();
 
-        let tokens = vec![Token::new("future".to_string())];
+        let document =
+            |id: &str| AnalysedDocument::new(id.to_string(), vec![Token::new("cop".to_string())]);
 
-        let results = inv_index.get_documents_from_tokens(tokens).unwrap();
+        for id in ["https://example.com/", "3f2a-9c1e", "https://example.com/"] {
+            inv_index.index(document(id)).unwrap();
+        }
 
-        assert_eq!(results.len(), 2);
+        // Documents are numbered densely, and a document that is indexed again is added as a new
+        // document, while the previous version is deleted.
+        assert_eq!(
+            inv_index.buffer.segment.docs().collect::<Vec<u32>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(inv_index.buffer.live_docs.len(), 2);
+        assert_eq!(
+            inv_index.buffer.segment.doc_number("https://example.com/"),
+            Some(2)
+        );
+
+        let results = inv_index
+            .retrieve(TermQuery::new("cop".to_string()).into())
+            .unwrap();
 
         assert_eq!(
             results,
-            vec![DocumentResult::from_id(0), DocumentResult::from_id(1)]
+            DocumentResult::from_ids(vec![
+                "3f2a-9c1e".to_string(),
+                "https://example.com/".to_string()
+            ])
         );
+    }
 
-        let tokens = vec![
-            Token::new("back".to_string()),
-            Token::new("again".to_string()),
-        ];
+    #[test]
+    fn can_delete() {
+        let mut inv_index = init_test_inv_index();
+
+        assert!(inv_index.delete("1"));
+        assert!(!inv_index.delete("1"));
+        assert!(!inv_index.delete("7"));
 
-        let results = inv_index.get_documents_from_tokens(tokens).unwrap();
+        let result = inv_index
+            .retrieve(TermQuery::new("future".to_string()).into())
+            .unwrap();
 
-        assert_eq!(results.len(), 2);
+        assert_eq!(result, vec![DocumentResult::from_id("0".to_string())]);
+        assert!(inv_index.stored_fields("1").is_none());
 
+        // The postings are only dropped by merging.
         assert_eq!(
-            results,
-            vec![DocumentResult::from_id(0), DocumentResult::from_id(2)]
+            inv_index
+                .get_docs_from_term(DEFAULT_FIELD, "future")
+                .unwrap(),
+            vec![0, 1]
         );
+        assert_eq!(
+            inv_index
+                .reader()
+                .term_statistics(DEFAULT_FIELD, "cop")
+                .unwrap()
+                .doc_freq,
+            1
+        );
+    }
 
-        let tokens = vec![Token::new("the".to_string())];
+    #[test]
+    fn can_compact() {
+        let mut inv_index = init_test_inv_index();
+
+        inv_index.delete("1");
+        inv_index.compact();
+
+        assert_eq!(inv_index.segments.len(), 1);
+        assert_eq!(inv_index.buffer.segment.max_doc(), 0);
 
-        let results = inv_index.get_documents_from_tokens(tokens).unwrap();
+        let segment = &inv_index.segments[0].segment;
+
+        assert_eq!(
+            segment.get_docs_from_term(DEFAULT_FIELD, "future").unwrap(),
+            vec![0]
+        );
+        assert!(segment
+            .get_docs_from_term(DEFAULT_FIELD, "cop")
+            .unwrap()
+            .is_empty());
+        assert_eq!(segment.docs().collect::<Vec<u32>>(), vec![0, 1]);
+        assert_eq!(
+            segment
+                .collection_statistics(DEFAULT_FIELD)
+                .sum_total_term_freq,
+            6
+        );
 
-        assert_eq!(results.len(), 1);
+        let result = inv_index
+            .retrieve(TermQuery::new("back".to_string()).into())
+            .unwrap();
 
-        assert_eq!(results, vec![DocumentResult::from_id(0)]);
+        assert_eq!(
+            result,
+            DocumentResult::from_ids(vec!["2".to_string(), "0".to_string()])
+        );
+    }
+
+    #[test]
+    fn deleted_document_can_be_indexed_again() {
+        let mut inv_index = init_test_inv_index();
+
+        inv_index.delete("2");
+        inv_index
+            .index(AnalysedDocument::new(
+                "2".to_string(),
+                vec![Token::new("cop".to_string())],
+            ))
+            .unwrap();
+
+        assert!(inv_index
+            .retrieve(TermQuery::new("back".to_string()).into())
+            .unwrap()
+            .iter()
+            .all(|result| result.id != "2"));
+        assert_eq!(
+            inv_index.get_postings(DEFAULT_FIELD, "cop").unwrap()[1].positions,
+            vec![0]
+        );
+
+        let result = inv_index
+            .retrieve(TermQuery::new("cop".to_string()).into())
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+    }
+
+    /// Indexes a document with the id and the text, tokenized on whitespace.
+    fn index_text(inv_index: &mut InvertedIndex, id: &str, text: &str) {
+        let tokens = text
+            .split_whitespace()
+            .map(|word| Token::new(word.to_string()))
+            .collect();
+
+        inv_index
+            .index(AnalysedDocument::new(id.to_string(), tokens))
+            .unwrap();
+    }
+
+    fn retrieve_ids(inv_index: &InvertedIndex, term: &str) -> Vec<String> {
+        inv_index
+            .retrieve(TermQuery::new(term.to_string()).into())
+            .unwrap()
+            .into_iter()
+            .map(|result| result.id)
+            .collect()
+    }
+
+    #[test]
+    fn flushes_full_buffer() {
+        let mut inv_index = InvertedIndex::new();
+        inv_index.set_merge_policy(Box::new(NoMergePolicy {}));
+        inv_index.set_max_buffered_docs(2);
+
+        for (id, text) in ["back to the future", "future cop", "back again"]
+            .iter()
+            .enumerate()
+        {
+            index_text(&mut inv_index, &id.to_string(), text);
+        }
+
+        assert_eq!(inv_index.segments.len(), 1);
+        assert_eq!(inv_index.segments[0].segment.name, "_0");
+        assert_eq!(inv_index.segments[0].segment.max_doc(), 2);
+        assert_eq!(inv_index.buffer.segment.max_doc(), 1);
+
+        // Replacing a document of a flushed segment deletes it there.
+        index_text(&mut inv_index, "1", "future again");
+
+        assert!(!inv_index.segments[0].live_docs.get(1));
+        assert_eq!(inv_index.segments.len(), 2);
+        assert_eq!(retrieve_ids(&inv_index, "cop"), Vec::<String>::new());
+        assert_eq!(retrieve_ids(&inv_index, "again"), vec!["2", "1"]);
+    }
+
+    #[test]
+    fn searches_across_segments() {
+        let texts = [
+            "back to the future",
+            "future cop",
+            "back again",
+            "the future of the past",
+            "cop again",
+        ];
+
+        let mut single = InvertedIndex::new();
+        let mut segmented = InvertedIndex::new();
+        segmented.set_merge_policy(Box::new(NoMergePolicy {}));
+        segmented.set_max_buffered_docs(2);
+
+        for (id, text) in texts.iter().enumerate() {
+            index_text(&mut single, &id.to_string(), text);
+            index_text(&mut segmented, &id.to_string(), text);
+        }
+
+        assert_eq!(segmented.segments.len(), 2);
+
+        // Statistics are of the whole index, so the scores do not depend on the segments.
+        for term in ["future", "back", "cop", "again", "the"] {
+            let query = || TermQuery::new(term.to_string()).into();
+
+            assert_eq!(
+                single.retrieve(query()).unwrap(),
+                segmented.retrieve(query()).unwrap()
+            );
+        }
+    }
+
+    fn init_merging_inv_index() -> InvertedIndex {
+        let mut inv_index = InvertedIndex::new();
+        inv_index.set_merge_policy(Box::new(
+            LogByteSizeMergePolicy::new()
+                .with_merge_factor(2)
+                .with_min_merge_bytes(1024),
+        ));
+        inv_index.set_max_buffered_docs(1);
+
+        inv_index
+    }
+
+    #[test]
+    fn merges_in_background() {
+        let mut inv_index = init_merging_inv_index();
+
+        for (id, text) in [
+            "back to the future",
+            "future cop",
+            "back again",
+            "cop again",
+        ]
+        .iter()
+        .enumerate()
+        {
+            index_text(&mut inv_index, &id.to_string(), text);
+        }
+
+        inv_index.delete("2");
+        inv_index.wait_for_merges();
+
+        // Four segments of one document are merged into two, and then into one, without the
+        // deleted document.
+        assert_eq!(inv_index.segments.len(), 1);
+        assert_eq!(inv_index.segments[0].segment.max_doc(), 3);
+        assert_eq!(inv_index.segments[0].live_docs.len(), 3);
+        assert!(inv_index.merging.is_empty());
+
+        assert_eq!(retrieve_ids(&inv_index, "again"), vec!["3"]);
+        assert_eq!(retrieve_ids(&inv_index, "cop"), vec!["1", "3"]);
+    }
+
+    #[test]
+    fn keeps_deletes_during_merge() {
+        let mut inv_index = init_merging_inv_index();
+
+        index_text(&mut inv_index, "0", "back to the future");
+        index_text(&mut inv_index, "1", "future cop");
+
+        // The merge of both segments has started, but is not applied before the delete.
+        assert_eq!(inv_index.merging.len(), 2);
+        assert!(inv_index.delete_doc("0"));
+
+        inv_index.wait_for_merges();
+
+        assert_eq!(inv_index.segments.len(), 1);
+        assert_eq!(inv_index.segments[0].live_docs.len(), 1);
+        assert_eq!(retrieve_ids(&inv_index, "future"), vec!["1"]);
+        assert!(inv_index.stored_fields("0").is_none());
+    }
+
+    #[test]
+    fn can_commit_and_open_segments() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let mut inv_index = InvertedIndex::new();
+        inv_index.set_merge_policy(Box::new(NoMergePolicy {}));
+        inv_index.set_max_buffered_docs(1);
+
+        index_text(&mut inv_index, "0", "back to the future");
+        index_text(&mut inv_index, "1", "future cop");
+        inv_index.commit(&directory, 0).unwrap();
+
+        assert!(directory.exists("_0.terms"));
+
+        inv_index.compact();
+        index_text(&mut inv_index, "2", "back again");
+        inv_index.delete("1");
+        inv_index.commit(&directory, 4).unwrap();
+
+        // The merged segments are gone, while the merged one is written.
+        assert!(!directory.exists("_0.terms"));
+        assert!(directory.exists("_2.terms"));
+        assert!(directory.exists("_3.terms"));
+
+        let opened = InvertedIndex::open(&directory, Box::<BM25Similarity>::default()).unwrap();
+
+        assert_eq!(opened.segments.len(), 2);
+        assert_eq!(opened.segment_acc, inv_index.segment_acc);
+        assert_eq!(opened.committed_seq_no(), 4);
+        assert_eq!(retrieve_ids(&opened, "future"), vec!["0"]);
+        assert_eq!(
+            retrieve_ids(&opened, "back"),
+            retrieve_ids(&inv_index, "back")
+        );
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn can_get_docs_from_term() {
+        let inv_index = init_test_inv_index();
+
+        assert_eq!(
+            inv_index
+                .get_docs_from_term(DEFAULT_FIELD, "future")
+                .unwrap(),
+            vec![0, 1]
+        );
+        assert_eq!(
+            inv_index.get_docs_from_term(DEFAULT_FIELD, "the").unwrap(),
+            vec![0]
+        );
+        assert!(inv_index
+            .get_docs_from_term(DEFAULT_FIELD, "present")
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
     fn can_retrieve() {
         let inv_index = init_test_inv_index();
 
-        let query = Query::new(
+        let query = BooleanQuery::from_tokens(
             vec![
                 Token::new("back".to_string()),
                 Token::new("future".to_string()),
@@ -241,11 +1032,169 @@ mod tests {
             vec![],
         );
 
-        let result = inv_index.retrieve(query).unwrap();
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        assert_eq!(result, vec![DocumentResult::from_id("0".to_string())]);
+    }
+
+    #[test]
+    fn missing_must_term_matches_nothing() {
+        let inv_index = init_test_inv_index();
+
+        let query = BooleanQuery::from_tokens(
+            vec![
+                Token::new("present".to_string()),
+                Token::new("future".to_string()),
+            ],
+            vec![],
+            vec![],
+        );
+
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn can_retrieve_should() {
+        let inv_index = init_test_inv_index();
+
+        let query = BooleanQuery::from_tokens(
+            vec![],
+            vec![],
+            vec![
+                Token::new("present".to_string()),
+                Token::new("back".to_string()),
+                Token::new("future".to_string()),
+            ],
+        );
+
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                DocumentResult::from_id("0".to_string()),
+                DocumentResult::from_id("1".to_string()),
+                DocumentResult::from_id("2".to_string())
+            ]
+        );
+
+        // Should clauses are optional next to must clauses, but rank the documents.
+        let query = BooleanQuery::from_tokens(
+            vec![Token::new("back".to_string())],
+            vec![],
+            vec![Token::new("again".to_string())],
+        );
+
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                DocumentResult::from_id("2".to_string()),
+                DocumentResult::from_id("0".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn can_retrieve_minimum_should_match() {
+        let inv_index = init_test_inv_index();
+
+        let query = BooleanQuery::from_tokens(
+            vec![],
+            vec![],
+            vec![
+                Token::new("back".to_string()),
+                Token::new("future".to_string()),
+                Token::new("cop".to_string()),
+            ],
+        )
+        .with_minimum_should_match(2);
+
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        // Document 1 is shorter, and contains the rarer term cop.
+        assert_eq!(
+            result,
+            vec![
+                DocumentResult::from_id("1".to_string()),
+                DocumentResult::from_id("0".to_string())
+            ]
+        );
+
+        let query = BooleanQuery::from_tokens(
+            vec![Token::new("future".to_string())],
+            vec![],
+            vec![Token::new("cop".to_string())],
+        )
+        .with_minimum_should_match(1);
+
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        assert_eq!(result, vec![DocumentResult::from_id("1".to_string())]);
+    }
+
+    #[test]
+    fn can_rank_with_bm25() {
+        let inv_index = init_test_inv_index();
+
+        let query = BooleanQuery::from_tokens(vec![], vec![], vec![Token::new("back".to_string())]);
 
+        let result = inv_index.retrieve(query.into()).unwrap();
+
+        // Both documents contain back once, but document 2 is shorter.
         assert_eq!(
             result,
-            vec![DocumentResult::from_id(0), DocumentResult::from_id(2)]
+            vec![
+                DocumentResult::from_id("2".to_string()),
+                DocumentResult::from_id("0".to_string())
+            ]
         );
+        assert!(result[0].score > result[1].score);
+    }
+
+    #[test]
+    fn can_configure_similarity() {
+        let mut inv_index = InvertedIndex::with_similarity(Box::new(BM25Similarity::new(1.2, 0.0)));
+
+        for doc in [
+            AnalysedDocument::new("0".to_string(), vec![Token::new("back".to_string())]),
+            AnalysedDocument::new(
+                "1".to_string(),
+                vec![
+                    Token::new("back".to_string()),
+                    Token::new("again".to_string()),
+                ],
+            ),
+        ] {
+            inv_index.index(doc).unwrap();
+        }
+
+        let scores = TermQuery::new("back".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        // Without length normalisation, document length does not matter.
+        assert_eq!(scores.get(&0), scores.get(&1));
+
+        let mut inv_index = InvertedIndex::with_similarity(Box::new(BooleanSimilarity {}));
+
+        inv_index
+            .index(AnalysedDocument::new(
+                "0".to_string(),
+                vec![
+                    Token::new("back".to_string()),
+                    Token::new("back".to_string()),
+                ],
+            ))
+            .unwrap();
+
+        let scores = TermQuery::new("back".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(scores.get(&0), Some(&1.0));
     }
 }
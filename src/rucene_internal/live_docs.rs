@@ -0,0 +1,113 @@
+/// A bitset with a bit per document number of a segment, which is set while the document is live:
+/// indexed and not deleted. Deleting a document only clears its bit, so its postings stay in the
+/// segment until it is merged.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct LiveDocs {
+    words: Vec<u64>,
+
+    // The number of set bits.
+    count: usize,
+}
+
+impl LiveDocs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A bitset with the documents up to `max_doc` live.
+    pub fn all(max_doc: u32) -> Self {
+        let mut live_docs = Self::new();
+
+        for doc in 0..max_doc {
+            live_docs.set(doc);
+        }
+
+        live_docs
+    }
+
+    /// A bitset from the words of another one, as returned by `words`.
+    pub fn from_words(words: Vec<u64>) -> Self {
+        let count = words.iter().map(|word| word.count_ones() as usize).sum();
+
+        LiveDocs { words, count }
+    }
+
+    /// The bits, 64 documents per word.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Whether the document is live.
+    pub fn get(&self, id: u32) -> bool {
+        let (word, bit) = Self::position(id);
+
+        self.words
+            .get(word)
+            .map(|word| word & bit != 0)
+            .unwrap_or(false)
+    }
+
+    /// Marks the document as live, growing the bitset if needed.
+    pub fn set(&mut self, id: u32) {
+        let (word, bit) = Self::position(id);
+
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+
+        if self.words[word] & bit == 0 {
+            self.words[word] |= bit;
+            self.count += 1;
+        }
+    }
+
+    /// Marks the document as deleted. Returns whether it was live.
+    pub fn clear(&mut self, id: u32) -> bool {
+        let (word, bit) = Self::position(id);
+
+        match self.words.get_mut(word) {
+            Some(word) if *word & bit != 0 => {
+                *word &= !bit;
+                self.count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The number of live documents.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    fn position(id: u32) -> (usize, u64) {
+        ((id / 64) as usize, 1 << (id % 64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::live_docs::LiveDocs;
+
+    #[test]
+    fn can_set_and_clear() {
+        let mut live_docs = LiveDocs::new();
+
+        live_docs.set(3);
+        live_docs.set(64);
+        live_docs.set(64);
+
+        assert!(live_docs.get(3));
+        assert!(live_docs.get(64));
+        assert!(!live_docs.get(4));
+        assert!(!live_docs.get(1000));
+        assert_eq!(live_docs.len(), 2);
+
+        assert!(live_docs.clear(3));
+        assert!(!live_docs.clear(3));
+        assert!(!live_docs.clear(1000));
+
+        assert!(!live_docs.get(3));
+        assert_eq!(live_docs.len(), 1);
+    }
+}
@@ -0,0 +1,452 @@
+//! Merge policies decide which segments are merged together, like in Lucene. Merging keeps the
+//! number of segments that a search has to visit small, and drops deleted documents.
+//! # Example
+//! ```
+//! use rucene::rucene_internal::merge_policy::LogByteSizeMergePolicy;
+//! use rucene::IndexWriter;
+//!
+//! let writer = IndexWriter::new().with_merge_policy(Box::new(LogByteSizeMergePolicy::default()));
+//! ```
+
+/// What a merge policy knows about a segment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentInfo {
+    /// An estimate of the bytes that the segment takes, including deleted documents.
+    pub size_in_bytes: u64,
+    /// Number of documents in the segment, including deleted documents.
+    pub max_doc: u32,
+    /// Number of deleted documents in the segment.
+    pub deleted_docs: u32,
+}
+
+impl SegmentInfo {
+    /// The share of the segment's documents that are deleted.
+    pub fn deletes_ratio(&self) -> f64 {
+        self.deleted_docs as f64 / self.max_doc.max(1) as f64
+    }
+
+    /// The estimated size without the deleted documents, which is the size after merging.
+    pub fn live_size_in_bytes(&self) -> u64 {
+        (self.size_in_bytes as f64 * (1.0 - self.deletes_ratio())) as u64
+    }
+}
+
+pub trait MergePolicy
+where
+    Self: Send + Sync,
+{
+    /// Returns the merges to run, each as the positions in `segments` of the segments to merge.
+    /// A position is in at most one merge. Segments that are being merged already are left out of
+    /// `segments`, which are otherwise in the order they were written.
+    fn find_merges(&self, segments: &[SegmentInfo]) -> Vec<Vec<usize>>;
+}
+
+/// Never merges segments, like Lucene's `NoMergePolicy`. Deleted documents are then only dropped
+/// by `IndexWriter::compact`.
+#[derive(Debug, Clone, Default)]
+pub struct NoMergePolicy {}
+
+impl MergePolicy for NoMergePolicy {
+    fn find_merges(&self, _segments: &[SegmentInfo]) -> Vec<Vec<usize>> {
+        vec![]
+    }
+}
+
+/// Merges segments of about the same size, allowing a number of segments per tier of sizes. This
+/// is the default merge policy, like in Lucene. Segments need not be adjacent to be merged.
+#[derive(Debug, Clone)]
+pub struct TieredMergePolicy {
+    segments_per_tier: usize,
+    max_merge_at_once: usize,
+    floor_segment_bytes: u64,
+    max_merged_segment_bytes: u64,
+    deletes_pct_allowed: f64,
+}
+
+impl TieredMergePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of segments allowed per tier. Smaller values mean more merging, but fewer
+    /// segments to search.
+    pub fn with_segments_per_tier(mut self, segments_per_tier: usize) -> Self {
+        self.segments_per_tier = segments_per_tier.max(2);
+        self
+    }
+
+    /// The maximum number of segments merged at once.
+    pub fn with_max_merge_at_once(mut self, max_merge_at_once: usize) -> Self {
+        self.max_merge_at_once = max_merge_at_once.max(2);
+        self
+    }
+
+    /// Segments smaller than this are treated as this size, so that tiny segments are merged
+    /// eagerly rather than piling up.
+    pub fn with_floor_segment_bytes(mut self, floor_segment_bytes: u64) -> Self {
+        self.floor_segment_bytes = floor_segment_bytes;
+        self
+    }
+
+    /// The maximum size of a merged segment. Segments of over half this size are no longer
+    /// merged.
+    pub fn with_max_merged_segment_bytes(mut self, max_merged_segment_bytes: u64) -> Self {
+        self.max_merged_segment_bytes = max_merged_segment_bytes;
+        self
+    }
+
+    /// The percentage of deleted documents in the index that is allowed before segments are
+    /// merged to drop them, even when there are not too many segments.
+    pub fn with_deletes_pct_allowed(mut self, deletes_pct_allowed: f64) -> Self {
+        self.deletes_pct_allowed = deletes_pct_allowed;
+        self
+    }
+
+    /// The number of segments that the index may have, given its size: a tier of segments of the
+    /// floor size, then a tier of segments `max_merge_at_once` times as large, and so on.
+    fn allowed_segment_count(&self, segments: &[SegmentInfo]) -> usize {
+        let total_bytes: u64 = segments.iter().map(|s| self.floored_size(s)).sum();
+        let mut level_bytes = segments
+            .iter()
+            .map(|s| self.floored_size(s))
+            .min()
+            .unwrap_or(self.floor_segment_bytes)
+            .max(1) as f64;
+        let mut bytes_left = total_bytes as f64;
+        let mut allowed = 0.0;
+
+        loop {
+            let level_count = bytes_left / level_bytes;
+
+            if level_count < self.segments_per_tier as f64
+                || level_bytes >= self.max_merged_segment_bytes as f64
+            {
+                allowed += level_count.ceil();
+                break;
+            }
+
+            allowed += self.segments_per_tier as f64;
+            bytes_left -= self.segments_per_tier as f64 * level_bytes;
+            level_bytes *= self.max_merge_at_once as f64;
+        }
+
+        (allowed as usize).max(self.segments_per_tier)
+    }
+
+    fn floored_size(&self, segment: &SegmentInfo) -> u64 {
+        segment.live_size_in_bytes().max(self.floor_segment_bytes)
+    }
+
+    /// Scores a merge: lower is better. Merges of equally sized segments are preferred, since
+    /// merging a large segment with small ones rewrites a lot for little gain, and so are merges
+    /// that drop many deleted documents.
+    fn score(&self, merge: &[&SegmentInfo]) -> f64 {
+        let total_before: u64 = merge.iter().map(|s| s.size_in_bytes).sum();
+        let total_after: u64 = merge.iter().map(|s| s.live_size_in_bytes()).sum();
+        let total_floored: u64 = merge.iter().map(|s| self.floored_size(s)).sum();
+        let largest_floored = merge
+            .iter()
+            .map(|s| self.floored_size(s))
+            .max()
+            .unwrap_or(0);
+
+        let skew = if merge.len() >= self.max_merge_at_once {
+            // A full merge is as balanced as it gets.
+            1.0 / self.max_merge_at_once as f64
+        } else {
+            largest_floored as f64 / total_floored.max(1) as f64
+        };
+        let live_ratio = total_after as f64 / total_before.max(1) as f64;
+
+        skew * (total_after.max(1) as f64).powf(0.05) * live_ratio.powi(2)
+    }
+}
+
+impl Default for TieredMergePolicy {
+    fn default() -> Self {
+        TieredMergePolicy {
+            segments_per_tier: 10,
+            max_merge_at_once: 10,
+            floor_segment_bytes: 2 * 1024 * 1024,
+            max_merged_segment_bytes: 5 * 1024 * 1024 * 1024,
+            deletes_pct_allowed: 20.0,
+        }
+    }
+}
+
+impl MergePolicy for TieredMergePolicy {
+    fn find_merges(&self, segments: &[SegmentInfo]) -> Vec<Vec<usize>> {
+        // Segments that are large enough already are not merged anymore.
+        let mut eligible: Vec<usize> = (0..segments.len())
+            .filter(|i| segments[*i].live_size_in_bytes() <= self.max_merged_segment_bytes / 2)
+            .collect();
+        eligible.sort_by_key(|i| std::cmp::Reverse(segments[*i].live_size_in_bytes()));
+
+        let allowed = self.allowed_segment_count(segments);
+
+        let max_doc: u64 = segments.iter().map(|s| s.max_doc as u64).sum();
+        let deleted_docs: u64 = segments.iter().map(|s| s.deleted_docs as u64).sum();
+        let mut deletes_pct = deleted_docs as f64 * 100.0 / max_doc.max(1) as f64;
+
+        let mut merges = vec![];
+
+        loop {
+            let segment_count =
+                segments.len() - merges.iter().map(Vec::len).sum::<usize>() + merges.len();
+            let too_many_segments = segment_count > allowed;
+
+            if !too_many_segments && deletes_pct <= self.deletes_pct_allowed {
+                break;
+            }
+
+            // Try each window of segments of about the same size, from large to small.
+            let mut best: Option<(f64, Vec<usize>)> = None;
+
+            for start in 0..eligible.len() {
+                let mut candidate = vec![];
+                let mut candidate_bytes = 0;
+
+                for i in &eligible[start..] {
+                    if candidate.len() == self.max_merge_at_once {
+                        break;
+                    }
+
+                    let bytes = segments[*i].live_size_in_bytes();
+
+                    if candidate_bytes + bytes > self.max_merged_segment_bytes {
+                        continue;
+                    }
+
+                    candidate.push(*i);
+                    candidate_bytes += bytes;
+                }
+
+                // Merging a single segment only helps to drop its deleted documents.
+                let useful = match candidate.as_slice() {
+                    [] => false,
+                    [i] => !too_many_segments && segments[*i].deleted_docs > 0,
+                    _ => true,
+                };
+
+                if !useful {
+                    continue;
+                }
+
+                let infos: Vec<&SegmentInfo> = candidate.iter().map(|i| &segments[*i]).collect();
+                let score = self.score(&infos);
+
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_score, _)| score < *best_score)
+                {
+                    best = Some((score, candidate));
+                }
+            }
+
+            let Some((_, merge)) = best else {
+                break;
+            };
+
+            eligible.retain(|i| !merge.contains(i));
+
+            let merged_deletes: u64 = merge.iter().map(|i| segments[*i].deleted_docs as u64).sum();
+            deletes_pct -= merged_deletes as f64 * 100.0 / max_doc.max(1) as f64;
+
+            merges.push(merge);
+        }
+
+        merges
+    }
+}
+
+/// Merges `merge_factor` adjacent segments of the same level, where levels are logarithmic in
+/// segment size, like Lucene's `LogByteSizeMergePolicy`.
+#[derive(Debug, Clone)]
+pub struct LogByteSizeMergePolicy {
+    merge_factor: usize,
+    min_merge_bytes: u64,
+    max_merge_bytes: u64,
+}
+
+// Segments whose level is at most this much below the highest level are on the same level.
+const LEVEL_LOG_SPAN: f64 = 0.75;
+
+impl LogByteSizeMergePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of segments merged at once, and the ratio between the sizes of levels.
+    pub fn with_merge_factor(mut self, merge_factor: usize) -> Self {
+        self.merge_factor = merge_factor.max(2);
+        self
+    }
+
+    /// Segments smaller than this are all on the lowest level.
+    pub fn with_min_merge_bytes(mut self, min_merge_bytes: u64) -> Self {
+        self.min_merge_bytes = min_merge_bytes;
+        self
+    }
+
+    /// Segments larger than this are no longer merged.
+    pub fn with_max_merge_bytes(mut self, max_merge_bytes: u64) -> Self {
+        self.max_merge_bytes = max_merge_bytes;
+        self
+    }
+}
+
+impl Default for LogByteSizeMergePolicy {
+    fn default() -> Self {
+        LogByteSizeMergePolicy {
+            merge_factor: 10,
+            min_merge_bytes: 1024 * 1024,
+            max_merge_bytes: 2 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl MergePolicy for LogByteSizeMergePolicy {
+    fn find_merges(&self, segments: &[SegmentInfo]) -> Vec<Vec<usize>> {
+        let norm = (self.merge_factor as f64).ln();
+        let level = |segment: &SegmentInfo| {
+            (segment
+                .live_size_in_bytes()
+                .max(self.min_merge_bytes)
+                .max(1) as f64)
+                .ln()
+                / norm
+        };
+        let levels: Vec<f64> = segments.iter().map(level).collect();
+        let lowest_level = (self.min_merge_bytes.max(1) as f64).ln() / norm;
+
+        let mut merges = vec![];
+        let mut start = 0;
+
+        // Find the highest level of the remaining segments, and merge runs of `merge_factor`
+        // segments down to the last segment on that level.
+        while start < segments.len() {
+            let max_level = levels[start..].iter().copied().fold(f64::MIN, f64::max);
+
+            let level_bottom = if max_level <= lowest_level {
+                f64::MIN
+            } else {
+                (max_level - LEVEL_LOG_SPAN).max(lowest_level)
+            };
+
+            let mut upto = segments.len() - 1;
+            while upto > start && levels[upto] < level_bottom {
+                upto -= 1;
+            }
+
+            let mut end = start + self.merge_factor;
+
+            while end <= upto + 1 {
+                let too_large = segments[start..end]
+                    .iter()
+                    .any(|segment| segment.live_size_in_bytes() >= self.max_merge_bytes);
+
+                if !too_large {
+                    merges.push((start..end).collect());
+                }
+
+                start = end;
+                end = start + self.merge_factor;
+            }
+
+            start = upto + 1;
+        }
+
+        merges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::merge_policy::{
+        LogByteSizeMergePolicy, MergePolicy, SegmentInfo, TieredMergePolicy,
+    };
+
+    fn segment(size_in_bytes: u64) -> SegmentInfo {
+        SegmentInfo {
+            size_in_bytes,
+            max_doc: 10,
+            deleted_docs: 0,
+        }
+    }
+
+    #[test]
+    fn tiered_merges_small_segments() {
+        let policy = TieredMergePolicy::new()
+            .with_segments_per_tier(3)
+            .with_max_merge_at_once(3)
+            .with_floor_segment_bytes(100);
+
+        // Few segments are allowed.
+        assert!(policy.find_merges(&[segment(100), segment(100)]).is_empty());
+
+        // One merge brings the index within budget, and it merges small segments rather than the
+        // large one.
+        let mut segments = vec![segment(1000)];
+        segments.extend([segment(100); 7]);
+
+        assert_eq!(policy.find_merges(&segments), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn tiered_merges_to_drop_deletes() {
+        let policy = TieredMergePolicy::new().with_deletes_pct_allowed(20.0);
+
+        let deleted = SegmentInfo {
+            deleted_docs: 5,
+            ..segment(100)
+        };
+
+        assert_eq!(policy.find_merges(&[segment(100), deleted]), vec![vec![1]]);
+        assert!(policy.find_merges(&[segment(100), segment(100)]).is_empty());
+    }
+
+    #[test]
+    fn tiered_does_not_merge_large_segments() {
+        let policy = TieredMergePolicy::new()
+            .with_segments_per_tier(2)
+            .with_max_merge_at_once(2)
+            .with_floor_segment_bytes(1)
+            .with_max_merged_segment_bytes(1000);
+
+        assert!(policy
+            .find_merges(&[segment(600), segment(600), segment(600)])
+            .is_empty());
+    }
+
+    #[test]
+    fn log_byte_size_merges_levels() {
+        let policy = LogByteSizeMergePolicy::new()
+            .with_merge_factor(3)
+            .with_min_merge_bytes(10)
+            .with_max_merge_bytes(100_000);
+
+        // Not enough segments on the lowest level.
+        assert!(policy.find_merges(&[segment(10), segment(10)]).is_empty());
+
+        let segments = [
+            segment(1000),
+            segment(10),
+            segment(10),
+            segment(10),
+            segment(10),
+        ];
+
+        // The large segment is a level of its own, and merge_factor segments of the lowest level
+        // are merged, in order.
+        assert_eq!(policy.find_merges(&segments), vec![vec![1, 2, 3]]);
+
+        let segments = [segment(1000), segment(900), segment(1000), segment(10)];
+
+        assert_eq!(policy.find_merges(&segments), vec![vec![0, 1, 2]]);
+
+        // Segments over the maximum size are not merged.
+        let segments = [segment(200_000), segment(200_000), segment(200_000)];
+
+        assert!(policy.find_merges(&segments).is_empty());
+    }
+}
@@ -1,5 +1,19 @@
+pub mod analysis;
+pub(crate) mod automaton;
 pub mod document;
+pub mod fst;
 pub(crate) mod inverted_index;
+pub(crate) mod live_docs;
+pub mod merge_policy;
+pub(crate) mod postings;
 pub mod query;
+pub mod query_parser;
+pub mod schema;
+pub(crate) mod segment;
+pub mod similarity;
+pub mod simple_query_parser;
+pub(crate) mod store;
+pub(crate) mod term_dictionary;
 pub mod token;
+pub mod translog;
 pub mod utils;
@@ -0,0 +1,77 @@
+use crate::rucene_internal::token::Offsets;
+
+/// The occurrences of a term in a single document.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Posting {
+    // The number of the document, rather than its ID.
+    pub doc_id: u32,
+
+    // Positions of the term in the document's token stream, in ascending order.
+    pub positions: Vec<u32>,
+
+    // Offsets of each occurrence, if the tokens had them.
+    pub offsets: Option<Vec<Offsets>>,
+}
+
+impl Posting {
+    pub fn new(doc_id: u32) -> Self {
+        Posting {
+            doc_id,
+            positions: vec![],
+            offsets: Some(vec![]),
+        }
+    }
+
+    /// Records an occurrence of the term. Offsets are only kept if every occurrence has them.
+    pub fn add_occurrence(&mut self, position: u32, offsets: Option<Offsets>) {
+        self.positions.push(position);
+
+        self.offsets = match (self.offsets.take(), offsets) {
+            (Some(mut all_offsets), Some(offsets)) => {
+                all_offsets.push(offsets);
+                Some(all_offsets)
+            }
+            _ => None,
+        };
+    }
+
+    /// How many times the term occurs in the document.
+    pub fn term_freq(&self) -> u32 {
+        self.positions.len() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::postings::Posting;
+    use crate::rucene_internal::token::Offsets;
+
+    #[test]
+    fn can_add_occurrences() {
+        let mut posting = Posting::new(0);
+
+        posting.add_occurrence(1, Some(Offsets { start: 4, end: 8 }));
+        posting.add_occurrence(3, Some(Offsets { start: 12, end: 16 }));
+
+        assert_eq!(posting.term_freq(), 2);
+        assert_eq!(posting.positions, vec![1, 3]);
+        assert_eq!(
+            posting.offsets,
+            Some(vec![
+                Offsets { start: 4, end: 8 },
+                Offsets { start: 12, end: 16 }
+            ])
+        );
+    }
+
+    #[test]
+    fn drops_incomplete_offsets() {
+        let mut posting = Posting::new(0);
+
+        posting.add_occurrence(0, Some(Offsets { start: 0, end: 4 }));
+        posting.add_occurrence(2, None);
+
+        assert_eq!(posting.term_freq(), 2);
+        assert_eq!(posting.offsets, None);
+    }
+}
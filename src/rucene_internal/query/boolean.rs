@@ -0,0 +1,194 @@
+use crate::rucene_internal::query::{DocScores, Query, TermQuery};
+use crate::rucene_internal::segment::SegmentReader;
+use crate::rucene_internal::token::Tokens;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Basically a copy of Lucene's `BooleanQuery`, with `must`, `must_not` and `should` clauses.
+/// # Example
+/// (taken from Relevant Search by Doug Turnbull and Jerry Berryman)
+/// ```
+///
+/// use rucene::rucene_internal::token::{Token};
+/// use rucene::rucene_internal::query::BooleanQuery;
+///
+/// let query = BooleanQuery::from_tokens(
+/// vec![
+///     Token::new("cat".to_string()),
+/// ],
+/// vec![Token::new("dog".to_string())],
+/// vec![Token::new("black".to_string())],
+/// );
+/// ```
+/// Documents:
+/// * my cat ran under the couch
+/// * black cats are mysterious
+/// * the dog scared the black cat
+///
+/// This query is looking for any documents that MUST contain cat, SHOULD contain black, and MUST_NOT contain dog.
+/// Therefore, both document (a) and (b) are matches because they contain the required term cat.
+/// Of these two documents, (b) will rank more highly than (a) because it contains the nonrequired term black.
+/// Even though document (c) contains both black and cat, it isn’t considered a match because it contains the disallowed term dog.
+///
+/// If there are no `must` clauses, a document has to match at least one `should` clause.
+/// `minimum_should_match` raises that number, and also applies when there are `must` clauses.
+/// Like in Lucene, a query with only `must_not` clauses matches nothing; combine it with
+/// `Query::MatchAll` to exclude documents from the whole index.
+#[derive(Debug, PartialEq)]
+pub struct BooleanQuery {
+    pub must: Vec<Query>,
+    pub must_not: Vec<Query>,
+    pub should: Vec<Query>,
+    pub minimum_should_match: usize,
+}
+
+impl BooleanQuery {
+    pub fn new(must: Vec<Query>, must_not: Vec<Query>, should: Vec<Query>) -> Self {
+        BooleanQuery {
+            must,
+            must_not,
+            should,
+            minimum_should_match: 0,
+        }
+    }
+
+    /// Builds a flat query, where every token is a `TermQuery` clause.
+    pub fn from_tokens(
+        must_tokens: Tokens,
+        must_not_tokens: Tokens,
+        should_tokens: Tokens,
+    ) -> Self {
+        let to_clauses = |tokens: Tokens| {
+            tokens
+                .into_iter()
+                .map(|token| Query::from(TermQuery::from(token)))
+                .collect()
+        };
+
+        Self::new(
+            to_clauses(must_tokens),
+            to_clauses(must_not_tokens),
+            to_clauses(should_tokens),
+        )
+    }
+
+    pub fn with_minimum_should_match(mut self, minimum_should_match: usize) -> Self {
+        self.minimum_should_match = minimum_should_match;
+        self
+    }
+
+    /// Scores are the sum of the scores of the matching `must` and `should` clauses, so
+    /// documents matching more optional clauses rank higher.
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        // Maps documents to their summed score and the number of `should` clauses they match.
+        let mut should_matches: BTreeMap<u32, (f32, usize)> = BTreeMap::new();
+
+        for clause in &self.should {
+            for (id, score) in clause.matches(index)? {
+                let entry = should_matches.entry(id).or_insert((0.0, 0));
+                entry.0 += score;
+                entry.1 += 1;
+            }
+        }
+
+        let mut results: DocScores = if self.must.is_empty() {
+            // Without required clauses, a document has to match at least one optional clause.
+            let minimum = self.minimum_should_match.max(1);
+
+            should_matches
+                .into_iter()
+                .filter(|(_, (_, count))| *count >= minimum)
+                .map(|(id, (score, _))| (id, score))
+                .collect()
+        } else {
+            self.get_must_matches(index)?
+                .into_iter()
+                .filter_map(|(id, score)| {
+                    let (should_score, count) = should_matches.get(&id).unwrap_or(&(0.0, 0));
+
+                    (*count >= self.minimum_should_match).then(|| (id, score + should_score))
+                })
+                .collect()
+        };
+
+        for clause in &self.must_not {
+            for id in clause.matches(index)?.keys() {
+                results.remove(id);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Returns the documents that match all of the `must` clauses.
+    fn get_must_matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let mut results: Option<DocScores> = None;
+
+        for clause in &self.must {
+            let clause_matches = clause.matches(index)?;
+
+            results = Some(match results {
+                None => clause_matches,
+                Some(matches) => matches
+                    .into_iter()
+                    .filter_map(|(id, score)| {
+                        clause_matches
+                            .get(&id)
+                            .map(|clause_score| (id, score + clause_score))
+                    })
+                    .collect(),
+            });
+        }
+
+        Ok(results.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_test_inv_index;
+    use crate::rucene_internal::query::{BooleanQuery, Query, TermQuery};
+
+    fn term(value: &str) -> Query {
+        Query::from(TermQuery::new(value.to_string()))
+    }
+
+    #[test]
+    fn can_match_nested() {
+        let inv_index = init_test_inv_index();
+
+        // (back OR cop) AND NOT (back AND again)
+        let query = BooleanQuery::new(
+            vec![Query::from(BooleanQuery::new(
+                vec![],
+                vec![],
+                vec![term("back"), term("cop")],
+            ))],
+            vec![Query::from(BooleanQuery::new(
+                vec![term("back"), term("again")],
+                vec![],
+                vec![],
+            ))],
+            vec![],
+        );
+
+        let results = query.matches(&inv_index.reader()).unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn only_must_not_matches_nothing() {
+        let inv_index = init_test_inv_index();
+
+        let query = BooleanQuery::new(vec![], vec![term("cop")], vec![]);
+
+        assert!(query.matches(&inv_index.reader()).unwrap().is_empty());
+
+        let query = BooleanQuery::new(vec![Query::MatchAll], vec![term("cop")], vec![]);
+
+        let results = query.matches(&inv_index.reader()).unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0, 2]);
+    }
+}
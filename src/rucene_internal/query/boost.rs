@@ -0,0 +1,51 @@
+use crate::rucene_internal::query::{DocScores, Query};
+use crate::rucene_internal::segment::SegmentReader;
+use std::error::Error;
+
+/// Matches the same documents as the wrapped query, with its scores multiplied by `boost`, e.g.
+/// to make one clause of a boolean query count more than the others.
+#[derive(Debug, PartialEq)]
+pub struct BoostQuery {
+    pub query: Box<Query>,
+    pub boost: f32,
+}
+
+impl BoostQuery {
+    pub fn new(query: Query, boost: f32) -> Self {
+        BoostQuery {
+            query: Box::new(query),
+            boost,
+        }
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        Ok(self
+            .query
+            .matches(index)?
+            .into_iter()
+            .map(|(id, score)| (id, score * self.boost))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_test_inv_index;
+    use crate::rucene_internal::query::{BoostQuery, Query, TermQuery};
+
+    #[test]
+    fn can_boost() {
+        let inv_index = init_test_inv_index();
+
+        let term = || Query::from(TermQuery::new("future".to_string()));
+
+        let scores = term().matches(&inv_index.reader()).unwrap();
+        let boosted_scores = BoostQuery::new(term(), 2.0)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(boosted_scores.len(), 2);
+        assert_eq!(boosted_scores[&0], scores[&0] * 2.0);
+        assert_eq!(boosted_scores[&1], scores[&1] * 2.0);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::rucene_internal::automaton::LevenshteinAutomaton;
+use crate::rucene_internal::query::{DocScores, TermQuery};
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use std::error::Error;
+
+/// How many edits a term may be away from the query term.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fuzziness {
+    /// A fixed edit distance, from 0 to 2.
+    Distance(u32),
+    /// Picks the distance from the length of the term, like Elasticsearch: terms of up to 2
+    /// characters must match exactly, terms of 3 to 5 characters may have one edit, and longer
+    /// terms may have two.
+    Auto,
+}
+
+impl Fuzziness {
+    pub fn distance(&self, term: &str) -> u32 {
+        match self {
+            Fuzziness::Distance(distance) => *distance,
+            Fuzziness::Auto => match term.chars().count() {
+                0..=2 => 0,
+                3..=5 => 1,
+                _ => 2,
+            },
+        }
+    }
+}
+
+/// Matches documents that contain terms similar to the query term, e.g. "fuuture" for "future".
+///
+/// Similar terms are found by intersecting a Levenshtein automaton with the term dictionary.
+/// The first `prefix_length` characters have to match exactly, which makes the intersection
+/// considerably cheaper. By default, swapping two adjacent characters counts as a single edit.
+///
+/// Only the `max_expansions` closest terms are searched. Each contributes its own score, lowered
+/// by how many edits away it is.
+#[derive(Debug, PartialEq)]
+pub struct FuzzyQuery {
+    pub field: String,
+    pub term: String,
+    pub fuzziness: Fuzziness,
+    pub prefix_length: usize,
+    pub transpositions: bool,
+    pub max_expansions: usize,
+}
+
+impl FuzzyQuery {
+    /// The highest edit distance supported, like in Lucene.
+    pub const MAX_DISTANCE: u32 = 2;
+
+    pub fn new(term: String) -> Self {
+        FuzzyQuery {
+            field: DEFAULT_FIELD.to_string(),
+            term,
+            fuzziness: Fuzziness::Auto,
+            prefix_length: 0,
+            transpositions: true,
+            max_expansions: 50,
+        }
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_fuzziness(mut self, fuzziness: Fuzziness) -> Self {
+        self.fuzziness = fuzziness;
+        self
+    }
+
+    pub fn with_prefix_length(mut self, prefix_length: usize) -> Self {
+        self.prefix_length = prefix_length;
+        self
+    }
+
+    pub fn with_transpositions(mut self, transpositions: bool) -> Self {
+        self.transpositions = transpositions;
+        self
+    }
+
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let max_distance = self.fuzziness.distance(&self.term);
+
+        if max_distance > Self::MAX_DISTANCE {
+            return Err(format!(
+                "Fuzziness {0} is larger than the maximum of {1}.",
+                max_distance,
+                Self::MAX_DISTANCE
+            )
+            .into());
+        }
+
+        let prefix: String = self.term.chars().take(self.prefix_length).collect();
+        let automaton = LevenshteinAutomaton::new(
+            &self.term[prefix.len()..],
+            max_distance,
+            self.transpositions,
+        );
+
+        let mut terms: Vec<(String, u32)> = index
+            .intersect_terms(&self.field, &prefix, &automaton)
+            .into_iter()
+            .map(|(term, state)| (term, automaton.distance(&state)))
+            .collect();
+
+        // The sort is stable, so terms with the same distance stay in order.
+        terms.sort_by_key(|(_, distance)| *distance);
+        terms.truncate(self.max_expansions);
+
+        let term_length = self.term.chars().count();
+        let mut results = DocScores::new();
+
+        for (term, distance) in terms {
+            let length = term_length.min(term.chars().count()).max(1);
+            let boost = (1.0 - distance as f32 / length as f32).max(0.0);
+
+            for (id, score) in TermQuery::new(term)
+                .with_field(self.field.clone())
+                .matches(index)?
+            {
+                *results.entry(id).or_insert(0.0) += score * boost;
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_inv_index_from_texts;
+    use crate::rucene_internal::inverted_index::InvertedIndex;
+    use crate::rucene_internal::query::{Fuzziness, FuzzyQuery};
+
+    fn init_inv_index() -> InvertedIndex {
+        init_inv_index_from_texts(&["future", "futures", "nature", "fun", "fan", "fiture"])
+    }
+
+    fn doc_ids(query: FuzzyQuery, inv_index: &InvertedIndex) -> Vec<u32> {
+        query
+            .matches(&inv_index.reader())
+            .unwrap()
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn can_match_fuzzy() {
+        let inv_index = init_inv_index();
+
+        let query = FuzzyQuery::new("fuuture".to_string()).with_fuzziness(Fuzziness::Distance(1));
+        assert_eq!(doc_ids(query, &inv_index), vec![0]);
+
+        let query = FuzzyQuery::new("fuuture".to_string()).with_fuzziness(Fuzziness::Distance(2));
+        assert_eq!(doc_ids(query, &inv_index), vec![0, 1, 5]);
+
+        let query = FuzzyQuery::new("future".to_string()).with_fuzziness(Fuzziness::Distance(0));
+        assert_eq!(doc_ids(query, &inv_index), vec![0]);
+    }
+
+    #[test]
+    fn can_pick_auto_fuzziness() {
+        assert_eq!(Fuzziness::Auto.distance("to"), 0);
+        assert_eq!(Fuzziness::Auto.distance("fun"), 1);
+        assert_eq!(Fuzziness::Auto.distance("future"), 2);
+
+        let inv_index = init_inv_index();
+
+        assert_eq!(
+            doc_ids(FuzzyQuery::new("fin".to_string()), &inv_index),
+            vec![3, 4]
+        );
+        assert_eq!(
+            doc_ids(FuzzyQuery::new("fuutre".to_string()), &inv_index),
+            vec![0, 1, 5]
+        );
+    }
+
+    #[test]
+    fn can_require_prefix() {
+        let inv_index = init_inv_index();
+
+        let query = FuzzyQuery::new("futrue".to_string()).with_prefix_length(2);
+        assert_eq!(doc_ids(query, &inv_index), vec![0, 1]);
+
+        let query = FuzzyQuery::new("fiture".to_string())
+            .with_fuzziness(Fuzziness::Distance(1))
+            .with_prefix_length(2);
+        assert_eq!(doc_ids(query, &inv_index), vec![5]);
+    }
+
+    #[test]
+    fn can_disable_transpositions() {
+        let inv_index = init_inv_index();
+
+        let query = FuzzyQuery::new("futrue".to_string()).with_fuzziness(Fuzziness::Distance(1));
+        assert_eq!(doc_ids(query, &inv_index), vec![0]);
+
+        let query = FuzzyQuery::new("futrue".to_string())
+            .with_fuzziness(Fuzziness::Distance(1))
+            .with_transpositions(false);
+        assert!(doc_ids(query, &inv_index).is_empty());
+    }
+
+    #[test]
+    fn closer_terms_score_higher() {
+        let inv_index = init_inv_index();
+
+        let results = FuzzyQuery::new("future".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results[&0] > results[&1]);
+        assert!(results[&0] > results[&5]);
+    }
+
+    #[test]
+    fn can_cap_expansions() {
+        let inv_index = init_inv_index();
+
+        let query = FuzzyQuery::new("futuress".to_string())
+            .with_fuzziness(Fuzziness::Distance(2))
+            .with_max_expansions(1);
+
+        assert_eq!(doc_ids(query, &inv_index), vec![1]);
+    }
+
+    #[test]
+    fn too_much_fuzziness_fails() {
+        let inv_index = init_inv_index();
+
+        assert!(FuzzyQuery::new("future".to_string())
+            .with_fuzziness(Fuzziness::Distance(3))
+            .matches(&inv_index.reader())
+            .is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::rucene_internal::query::{BooleanQuery, PhraseQuery, Query, TermQuery};
+use crate::rucene_internal::schema::Field;
+use crate::rucene_internal::token::Tokens;
+use std::error::Error;
+use std::str::FromStr;
+
+/// How the clauses of analysed text are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Operator {
+    /// Any of the clauses should match.
+    #[default]
+    Or,
+    /// All of the clauses must match.
+    And,
+}
+
+impl FromStr for Operator {
+    type Err = Box<dyn Error>;
+
+    /// Parses `or` or `and`, in any case.
+    fn from_str(operator: &str) -> Result<Self, Self::Err> {
+        match operator.to_lowercase().as_str() {
+            "or" => Ok(Operator::Or),
+            "and" => Ok(Operator::And),
+            _ => Err(format!("Unknown operator '{0}', should be 'or' or 'and'.", operator).into()),
+        }
+    }
+}
+
+/// Builds queries on a field from free text, like Lucene's `QueryBuilder`. The text goes through
+/// the search analyser of the field if it has one, or else the analyser the field was indexed
+/// with, so that e.g. "Future" matches the indexed term "future".
+#[derive(Clone, Copy)]
+pub struct MatchQueryBuilder<'a> {
+    field: &'a Field,
+    operator: Operator,
+}
+
+impl<'a> MatchQueryBuilder<'a> {
+    pub fn new(field: &'a Field) -> Self {
+        MatchQueryBuilder {
+            field,
+            operator: Operator::Or,
+        }
+    }
+
+    pub fn with_operator(mut self, operator: Operator) -> Self {
+        self.operator = operator;
+        self
+    }
+
+    pub fn analyse(&self, text: &str) -> Tokens {
+        self.field.analyser_for_search().analyse_text(text)
+    }
+
+    /// Builds a term query for each token, combined with the operator. Text without tokens
+    /// matches nothing.
+    pub fn build(&self, text: &str) -> Query {
+        let mut tokens = self.analyse(text);
+
+        match tokens.len() {
+            0 => Query::MatchNone,
+            1 => self.term(tokens.remove(0).value),
+            _ => {
+                let clauses = tokens
+                    .into_iter()
+                    .map(|token| self.term(token.value))
+                    .collect();
+
+                match self.operator {
+                    Operator::Or => Query::from(BooleanQuery::new(vec![], vec![], clauses)),
+                    Operator::And => Query::from(BooleanQuery::new(clauses, vec![], vec![])),
+                }
+            }
+        }
+    }
+
+    /// Builds a phrase query from the tokens. A single token is a term query, and text without
+    /// tokens matches nothing.
+    pub fn build_phrase(&self, text: &str, slop: u32) -> Query {
+        let mut tokens = self.analyse(text);
+
+        match tokens.len() {
+            0 => Query::MatchNone,
+            1 => self.term(tokens.remove(0).value),
+            _ => Query::from(
+                PhraseQuery::from_tokens(tokens)
+                    .with_field(self.field.name.clone())
+                    .with_slop(slop),
+            ),
+        }
+    }
+
+    /// Builds a term query on the field, for a term that has already been analysed.
+    pub fn term(&self, term: String) -> Query {
+        Query::from(TermQuery::new(term).with_field(self.field.name.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::analysis::{
+        Analyser, CharacterFilter, OffsetCorrections, StandardTokenizer,
+    };
+    use crate::rucene_internal::query::{
+        BooleanQuery, MatchQueryBuilder, Operator, PhraseQuery, Query, TermQuery,
+    };
+    use crate::rucene_internal::schema::Field;
+
+    fn term(term: &str) -> Query {
+        Query::from(TermQuery::new(term.to_string()))
+    }
+
+    fn body() -> Field {
+        Field::new("body".to_string())
+    }
+
+    #[test]
+    fn can_build_term_query() {
+        let field = body();
+        let builder = MatchQueryBuilder::new(&field);
+
+        assert_eq!(builder.build("<b>Future</b>"), term("future"));
+        assert_eq!(builder.build(" , "), Query::MatchNone);
+
+        let field = Field::new("title".to_string());
+
+        assert_eq!(
+            MatchQueryBuilder::new(&field).build("Future"),
+            Query::from(TermQuery::new("future".to_string()).with_field("title".to_string()))
+        );
+    }
+
+    #[test]
+    fn can_build_boolean_query() {
+        let field = body();
+        let builder = MatchQueryBuilder::new(&field);
+
+        assert_eq!(
+            builder.build("Back to the Future"),
+            Query::from(BooleanQuery::new(
+                vec![],
+                vec![],
+                vec![term("back"), term("to"), term("the"), term("future")]
+            ))
+        );
+        assert_eq!(
+            builder.with_operator(Operator::And).build("Future Cop"),
+            Query::from(BooleanQuery::new(
+                vec![term("future"), term("cop")],
+                vec![],
+                vec![]
+            ))
+        );
+    }
+
+    #[test]
+    fn can_build_phrase_query() {
+        let field = body();
+        let builder = MatchQueryBuilder::new(&field);
+
+        assert_eq!(
+            builder.build_phrase("Future Cop", 1),
+            Query::from(
+                PhraseQuery::new(vec!["future".to_string(), "cop".to_string()]).with_slop(1)
+            )
+        );
+        assert_eq!(builder.build_phrase("Cop", 1), term("cop"));
+        assert_eq!(builder.build_phrase("", 1), Query::MatchNone);
+    }
+
+    struct StopWordCharacterFilter {}
+
+    impl CharacterFilter for StopWordCharacterFilter {
+        fn filter(&self, input: &mut String, _corrections: &mut OffsetCorrections) {
+            // Only used to search, so the offsets do not matter.
+            *input = input.replace("the ", "");
+        }
+    }
+
+    #[test]
+    fn uses_search_analyser() {
+        let field = body().with_search_analyser(Analyser::new(
+            vec![Box::new(StopWordCharacterFilter {})],
+            Box::new(StandardTokenizer {}),
+            vec![],
+        ));
+
+        let builder = MatchQueryBuilder::new(&field);
+
+        assert_eq!(builder.build("the Future"), term("Future"));
+    }
+}
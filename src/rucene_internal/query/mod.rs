@@ -0,0 +1,182 @@
+//! Queries form a tree: leaf queries such as `TermQuery` match documents on their own, while a
+//! `BooleanQuery` combines child queries, which can be boolean queries themselves.
+//! # Example
+//! `(back OR again) AND NOT (future AND cop)`:
+//! ```
+//! use rucene::rucene_internal::query::{BooleanQuery, Query, TermQuery};
+//!
+//! let query = Query::from(BooleanQuery::new(
+//!     vec![Query::from(BooleanQuery::new(
+//!         vec![],
+//!         vec![],
+//!         vec![
+//!             Query::from(TermQuery::new("back".to_string())),
+//!             Query::from(TermQuery::new("again".to_string())),
+//!         ],
+//!     ))],
+//!     vec![Query::from(BooleanQuery::new(
+//!         vec![
+//!             Query::from(TermQuery::new("future".to_string())),
+//!             Query::from(TermQuery::new("cop".to_string())),
+//!         ],
+//!         vec![],
+//!         vec![],
+//!     ))],
+//!     vec![],
+//! ));
+//! ```
+
+use crate::rucene_internal::segment::SegmentReader;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+mod boolean;
+mod boost;
+mod fuzzy;
+mod match_query;
+mod multi_term;
+mod phrase;
+mod prefix;
+mod range;
+mod regexp;
+mod span;
+mod term;
+mod wildcard;
+
+pub use boolean::BooleanQuery;
+pub use boost::BoostQuery;
+pub use fuzzy::{Fuzziness, FuzzyQuery};
+pub use match_query::{MatchQueryBuilder, Operator};
+pub use multi_term::DEFAULT_MAX_EXPANSIONS;
+pub use phrase::PhraseQuery;
+pub use prefix::PrefixQuery;
+pub use range::TermRangeQuery;
+pub use regexp::RegexpQuery;
+pub use span::SpanQuery;
+pub use term::TermQuery;
+pub use wildcard::WildcardQuery;
+
+/// Maps the ID's of matching documents to their scores.
+pub(crate) type DocScores = BTreeMap<u32, f32>;
+
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    Term(TermQuery),
+    Boolean(BooleanQuery),
+    Phrase(PhraseQuery),
+    Span(SpanQuery),
+    Prefix(PrefixQuery),
+    Wildcard(WildcardQuery),
+    Regexp(RegexpQuery),
+    Fuzzy(FuzzyQuery),
+    TermRange(TermRangeQuery),
+    Boost(BoostQuery),
+    /// Matches every document in the index.
+    MatchAll,
+    /// Matches no documents.
+    MatchNone,
+}
+
+impl Query {
+    /// Returns the documents matching the query.
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        match self {
+            Query::Term(query) => query.matches(index),
+            Query::Boolean(query) => query.matches(index),
+            Query::Phrase(query) => query.matches(index),
+            Query::Span(query) => query.matches(index),
+            Query::Prefix(query) => query.matches(index),
+            Query::Wildcard(query) => query.matches(index),
+            Query::Regexp(query) => query.matches(index),
+            Query::Fuzzy(query) => query.matches(index),
+            Query::TermRange(query) => query.matches(index),
+            Query::Boost(query) => query.matches(index),
+            Query::MatchAll => Ok(index.docs().map(|doc| (doc, 1.0)).collect()),
+            Query::MatchNone => Ok(DocScores::new()),
+        }
+    }
+}
+
+impl From<TermQuery> for Query {
+    fn from(query: TermQuery) -> Self {
+        Query::Term(query)
+    }
+}
+
+impl From<BooleanQuery> for Query {
+    fn from(query: BooleanQuery) -> Self {
+        Query::Boolean(query)
+    }
+}
+
+impl From<PhraseQuery> for Query {
+    fn from(query: PhraseQuery) -> Self {
+        Query::Phrase(query)
+    }
+}
+
+impl From<SpanQuery> for Query {
+    fn from(query: SpanQuery) -> Self {
+        Query::Span(query)
+    }
+}
+
+impl From<PrefixQuery> for Query {
+    fn from(query: PrefixQuery) -> Self {
+        Query::Prefix(query)
+    }
+}
+
+impl From<WildcardQuery> for Query {
+    fn from(query: WildcardQuery) -> Self {
+        Query::Wildcard(query)
+    }
+}
+
+impl From<RegexpQuery> for Query {
+    fn from(query: RegexpQuery) -> Self {
+        Query::Regexp(query)
+    }
+}
+
+impl From<FuzzyQuery> for Query {
+    fn from(query: FuzzyQuery) -> Self {
+        Query::Fuzzy(query)
+    }
+}
+
+impl From<TermRangeQuery> for Query {
+    fn from(query: TermRangeQuery) -> Self {
+        Query::TermRange(query)
+    }
+}
+
+impl From<BoostQuery> for Query {
+    fn from(query: BoostQuery) -> Self {
+        Query::Boost(query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_test_inv_index;
+    use crate::rucene_internal::query::Query;
+
+    #[test]
+    fn can_match_all() {
+        let inv_index = init_test_inv_index();
+
+        let results = Query::MatchAll.matches(&inv_index.reader()).unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn can_match_none() {
+        let inv_index = init_test_inv_index();
+
+        let results = Query::MatchNone.matches(&inv_index.reader()).unwrap();
+
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::segment::SegmentReader;
+use std::error::Error;
+
+/// How many terms a multi-term query may expand to by default, like Lucene's maximum number of
+/// boolean clauses.
+pub const DEFAULT_MAX_EXPANSIONS: usize = 1024;
+
+/// Collects the terms a multi-term query expands to, failing once there are more than
+/// `max_expansions` of them, so a pattern like `a*` cannot blow up memory.
+pub(crate) fn expand_terms(
+    terms: impl Iterator<Item = String>,
+    max_expansions: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut expanded = vec![];
+
+    for term in terms {
+        if expanded.len() == max_expansions {
+            return Err(format!(
+                "Query expands to more than the maximum of {0} terms.",
+                max_expansions
+            )
+            .into());
+        }
+
+        expanded.push(term);
+    }
+
+    Ok(expanded)
+}
+
+/// Matches the documents that contain any of the terms. Like Lucene's default rewrite for
+/// multi-term queries, every match gets the same score, since the expanded terms are not what
+/// the user typed.
+pub(crate) fn constant_score_matches(
+    field: &str,
+    terms: &[String],
+    index: &SegmentReader,
+) -> Result<DocScores, Box<dyn Error>> {
+    let mut results = DocScores::new();
+
+    for term in terms {
+        for posting in index.get_postings(field, term)? {
+            results.insert(posting.doc_id, 1.0);
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_test_inv_index;
+    use crate::rucene_internal::query::multi_term::{constant_score_matches, expand_terms};
+    use crate::rucene_internal::schema::DEFAULT_FIELD;
+
+    #[test]
+    fn can_expand_terms() {
+        let terms = ["back", "to", "the"];
+
+        let expanded = expand_terms(terms.into_iter().map(str::to_string), 3).unwrap();
+
+        assert_eq!(expanded, vec!["back", "to", "the"]);
+    }
+
+    #[test]
+    fn too_many_expansions_fail() {
+        let terms = ["back", "to", "the"];
+
+        assert!(expand_terms(terms.into_iter().map(str::to_string), 2).is_err());
+    }
+
+    #[test]
+    fn can_match_constant_score() {
+        let inv_index = init_test_inv_index();
+
+        let terms = ["cop".to_string(), "again".to_string()];
+        let results = constant_score_matches(DEFAULT_FIELD, &terms, &inv_index.reader()).unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![1, 2]);
+        assert!(results.values().all(|score| *score == 1.0));
+    }
+}
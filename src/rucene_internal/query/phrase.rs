@@ -0,0 +1,253 @@
+use crate::rucene_internal::postings::Posting;
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use crate::rucene_internal::token::Tokens;
+use std::error::Error;
+
+/// Matches documents that contain the terms in the given order, e.g. "back to the future".
+///
+/// With a `slop`, the terms may be further apart or out of order: the slop is how far the terms
+/// may be moved from their place in the phrase, like in Lucene. For example, "back future" matches
+/// "back to the future" with a slop of 2.
+/// # Example
+/// ```
+/// use rucene::rucene_internal::query::PhraseQuery;
+///
+/// let query = PhraseQuery::new(vec!["back".to_string(), "future".to_string()]).with_slop(2);
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct PhraseQuery {
+    pub field: String,
+    pub terms: Vec<String>,
+    pub slop: u32,
+}
+
+impl PhraseQuery {
+    pub fn new(terms: Vec<String>) -> Self {
+        PhraseQuery {
+            field: DEFAULT_FIELD.to_string(),
+            terms,
+            slop: 0,
+        }
+    }
+
+    pub fn from_tokens(tokens: Tokens) -> Self {
+        Self::new(tokens.into_iter().map(|token| token.value).collect())
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_slop(mut self, slop: u32) -> Self {
+        self.slop = slop;
+        self
+    }
+
+    /// Documents are scored by how often the phrase occurs in them. Sloppy matches count less
+    /// the further the terms had to be moved.
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        if self.terms.is_empty() {
+            return Ok(DocScores::new());
+        }
+
+        let mut term_postings: Vec<&[Posting]> = vec![];
+        let mut term_statistics = vec![];
+
+        for term in &self.terms {
+            term_postings.push(index.get_postings(&self.field, term)?);
+            term_statistics.push(index.term_statistics(&self.field, term)?);
+        }
+
+        let mut results = DocScores::new();
+
+        // Only documents that contain every term can contain the phrase.
+        for posting in term_postings[0] {
+            let doc_postings: Option<Vec<&Posting>> = term_postings
+                .iter()
+                .map(|postings| {
+                    postings
+                        .binary_search_by_key(&posting.doc_id, |p| p.doc_id)
+                        .ok()
+                        .map(|pos| &postings[pos])
+                })
+                .collect();
+
+            if let Some(doc_postings) = doc_postings {
+                let freq = self.phrase_freq(&doc_postings);
+
+                if freq > 0.0 {
+                    let score = index.score(&self.field, posting.doc_id, freq, &term_statistics);
+                    results.insert(posting.doc_id, score);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Shifts the positions of each term by its place in the phrase, so an exact match has the
+    /// same position for every term. Then every window of positions, one per term, that fits in
+    /// the slop is a match, weighted by `1 / (distance + 1)`. Like in Lucene, a term that is in the
+    /// phrase more than once has to match a different position each time, so that `"a a"~1` does
+    /// not match a single "a".
+    fn phrase_freq(&self, doc_postings: &[&Posting]) -> f32 {
+        let relative_positions: Vec<Vec<i64>> = doc_postings
+            .iter()
+            .enumerate()
+            .map(|(offset, posting)| {
+                posting
+                    .positions
+                    .iter()
+                    .map(|position| *position as i64 - offset as i64)
+                    .collect()
+            })
+            .collect();
+
+        let mut cursors = vec![0; relative_positions.len()];
+        let mut freq = 0.0;
+
+        loop {
+            let current = cursors
+                .iter()
+                .zip(&relative_positions)
+                .map(|(cursor, positions)| positions[*cursor]);
+
+            // The positions in the document, to tell whether repeated terms are at the same one.
+            let positions: Vec<i64> = current
+                .clone()
+                .enumerate()
+                .map(|(offset, position)| position + offset as i64)
+                .collect();
+
+            let (min_term, min) = current
+                .clone()
+                .enumerate()
+                .min_by_key(|(_, position)| *position)
+                .unwrap();
+            let max = current.max().unwrap();
+
+            let distance = (max - min) as u32;
+
+            if distance <= self.slop && self.distinct_positions(&positions) {
+                freq += 1.0 / (distance as f32 + 1.0);
+            }
+
+            // Move the term that lags behind, until one of the terms runs out of positions.
+            cursors[min_term] += 1;
+
+            if cursors[min_term] == relative_positions[min_term].len() {
+                return freq;
+            }
+        }
+    }
+
+    /// Whether the terms that are in the phrase more than once are at different positions.
+    fn distinct_positions(&self, positions: &[i64]) -> bool {
+        (0..positions.len()).all(|i| {
+            (i + 1..positions.len())
+                .all(|j| self.terms[i] != self.terms[j] || positions[i] != positions[j])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::{
+        init_inv_index_from_texts, init_test_inv_index,
+    };
+    use crate::rucene_internal::query::PhraseQuery;
+
+    fn phrase(terms: &[&str]) -> PhraseQuery {
+        PhraseQuery::new(terms.iter().map(|term| term.to_string()).collect())
+    }
+
+    #[test]
+    fn can_match_phrase() {
+        let inv_index = init_test_inv_index();
+
+        let results = phrase(&["back", "to", "the", "future"])
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0]);
+
+        // Both terms are in document 0, but not next to each other.
+        let results = phrase(&["back", "future"])
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results.is_empty());
+
+        let results = phrase(&["future", "back"])
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn can_match_phrase_with_slop() {
+        let inv_index = init_test_inv_index();
+
+        let results = phrase(&["back", "future"])
+            .with_slop(1)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results.is_empty());
+
+        let results = phrase(&["back", "future"])
+            .with_slop(2)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0]);
+
+        // Out of order matches need more slop.
+        let results = phrase(&["future", "back"])
+            .with_slop(4)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0]);
+    }
+
+    #[test]
+    fn sloppy_matches_score_lower() {
+        let inv_index = init_test_inv_index();
+
+        let exact = phrase(&["to", "the"]).matches(&inv_index.reader()).unwrap();
+        let sloppy = phrase(&["back", "the"])
+            .with_slop(1)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(exact[&0] > sloppy[&0]);
+    }
+
+    #[test]
+    fn repeated_terms_match_different_positions() {
+        let inv_index = init_inv_index_from_texts(&["future", "future future"]);
+
+        let results = phrase(&["future", "future"])
+            .with_slop(1)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![1]);
+    }
+
+    #[test]
+    fn missing_terms_match_nothing() {
+        let inv_index = init_test_inv_index();
+
+        assert!(phrase(&["back", "present"])
+            .matches(&inv_index.reader())
+            .unwrap()
+            .is_empty());
+        assert!(phrase(&[]).matches(&inv_index.reader()).unwrap().is_empty());
+    }
+}
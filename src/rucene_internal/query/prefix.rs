@@ -0,0 +1,105 @@
+use crate::rucene_internal::query::multi_term::{
+    constant_score_matches, expand_terms, DEFAULT_MAX_EXPANSIONS,
+};
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use std::error::Error;
+
+/// Matches documents that contain a term starting with the prefix, e.g. `fut*`.
+#[derive(Debug, PartialEq)]
+pub struct PrefixQuery {
+    pub field: String,
+    pub prefix: String,
+    pub max_expansions: usize,
+
+    // Whether only the first `max_expansions` terms are searched when there are more, rather
+    // than failing.
+    pub truncate: bool,
+}
+
+impl PrefixQuery {
+    pub fn new(prefix: String) -> Self {
+        PrefixQuery {
+            field: DEFAULT_FIELD.to_string(),
+            prefix,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+            truncate: false,
+        }
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    /// Searches only the first `max_expansions` terms with the prefix, in term order, if it has
+    /// more, rather than failing. For input that should never fail, like a search box.
+    pub fn with_truncation(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let terms = index.terms_with_prefix(&self.field, &self.prefix);
+
+        let terms = if self.truncate {
+            terms.take(self.max_expansions).collect()
+        } else {
+            expand_terms(terms, self.max_expansions)?
+        };
+
+        constant_score_matches(&self.field, &terms, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_inv_index_from_texts;
+    use crate::rucene_internal::query::PrefixQuery;
+
+    #[test]
+    fn can_match_prefix() {
+        let inv_index = init_inv_index_from_texts(&["future", "futile", "fun", "refuture"]);
+
+        let results = PrefixQuery::new("fut".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn can_cap_expansions() {
+        let inv_index = init_inv_index_from_texts(&["future", "futile", "fun", "refuture"]);
+
+        assert!(PrefixQuery::new("f".to_string())
+            .with_max_expansions(2)
+            .matches(&inv_index.reader())
+            .is_err());
+
+        assert!(PrefixQuery::new("fut".to_string())
+            .with_max_expansions(2)
+            .matches(&inv_index.reader())
+            .is_ok());
+    }
+
+    #[test]
+    fn can_truncate_expansions() {
+        let inv_index = init_inv_index_from_texts(&["future", "futile", "fun", "refuture"]);
+
+        // Only "fun" and "futile", the first two terms with the prefix.
+        let results = PrefixQuery::new("f".to_string())
+            .with_max_expansions(2)
+            .with_truncation(true)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![1, 2]);
+    }
+}
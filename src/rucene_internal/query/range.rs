@@ -0,0 +1,117 @@
+use crate::rucene_internal::query::multi_term::{
+    constant_score_matches, expand_terms, DEFAULT_MAX_EXPANSIONS,
+};
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use std::error::Error;
+use std::ops::Bound;
+
+/// Matches documents that contain a term within the lexicographic bounds, e.g.
+/// `[aardvark TO azure}` for terms from aardvark (inclusive) up to azure (exclusive).
+/// Useful for keyword-like terms such as SKU's and codes.
+/// # Example
+/// ```
+/// use rucene::rucene_internal::query::TermRangeQuery;
+/// use std::ops::Bound;
+///
+/// let query = TermRangeQuery::new(
+///     Bound::Included("aardvark".to_string()),
+///     Bound::Excluded("azure".to_string()),
+/// );
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct TermRangeQuery {
+    pub field: String,
+    pub lower: Bound<String>,
+    pub upper: Bound<String>,
+    pub max_expansions: usize,
+}
+
+impl TermRangeQuery {
+    pub fn new(lower: Bound<String>, upper: Bound<String>) -> Self {
+        TermRangeQuery {
+            field: DEFAULT_FIELD.to_string(),
+            lower,
+            upper,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let terms = expand_terms(
+            index.terms_in_range(
+                &self.field,
+                self.lower.as_ref().map(String::as_str),
+                self.upper.as_ref().map(String::as_str),
+            ),
+            self.max_expansions,
+        )?;
+
+        constant_score_matches(&self.field, &terms, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_inv_index_from_texts;
+    use crate::rucene_internal::query::TermRangeQuery;
+    use std::ops::Bound;
+
+    fn doc_ids(lower: Bound<&str>, upper: Bound<&str>) -> Vec<u32> {
+        let inv_index = init_inv_index_from_texts(&["aardvark", "azure", "ab-100", "ab-200", "b"]);
+
+        TermRangeQuery::new(lower.map(str::to_string), upper.map(str::to_string))
+            .matches(&inv_index.reader())
+            .unwrap()
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn can_match_range() {
+        assert_eq!(
+            doc_ids(Bound::Included("aardvark"), Bound::Excluded("azure")),
+            vec![0, 2, 3]
+        );
+        assert_eq!(
+            doc_ids(Bound::Excluded("aardvark"), Bound::Included("azure")),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            doc_ids(Bound::Included("ab-100"), Bound::Included("ab-150")),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn can_match_open_range() {
+        assert_eq!(
+            doc_ids(Bound::Included("azure"), Bound::Unbounded),
+            vec![1, 4]
+        );
+        assert_eq!(doc_ids(Bound::Unbounded, Bound::Excluded("ab")), vec![0]);
+        assert_eq!(
+            doc_ids(Bound::Unbounded, Bound::Unbounded),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn inverted_range_matches_nothing() {
+        assert!(doc_ids(Bound::Included("b"), Bound::Included("a")).is_empty());
+        assert!(doc_ids(Bound::Excluded("b"), Bound::Excluded("b")).is_empty());
+        assert_eq!(doc_ids(Bound::Included("b"), Bound::Included("b")), vec![4]);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::rucene_internal::query::multi_term::{
+    constant_score_matches, expand_terms, DEFAULT_MAX_EXPANSIONS,
+};
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use regex::Regex;
+use std::error::Error;
+
+/// Matches documents that contain a term matching the regular expression, e.g. `fut(ure|ile)`.
+/// Like in Lucene, the expression has to match the whole term. An invalid expression fails the
+/// search.
+#[derive(Debug, PartialEq)]
+pub struct RegexpQuery {
+    pub field: String,
+    pub pattern: String,
+    pub max_expansions: usize,
+}
+
+impl RegexpQuery {
+    pub fn new(pattern: String) -> Self {
+        RegexpQuery {
+            field: DEFAULT_FIELD.to_string(),
+            pattern,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let regex = Regex::new(&format!("^(?:{0})$", self.pattern))?;
+
+        let terms = expand_terms(
+            index
+                .terms_with_prefix(&self.field, "")
+                .filter(|term| regex.is_match(term)),
+            self.max_expansions,
+        )?;
+
+        constant_score_matches(&self.field, &terms, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_inv_index_from_texts;
+    use crate::rucene_internal::query::RegexpQuery;
+
+    #[test]
+    fn can_match_regexp() {
+        let inv_index = init_inv_index_from_texts(&["future", "futile", "fun", "refuture"]);
+
+        let results = RegexpQuery::new("fut(ure|ile)".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0, 1]);
+
+        // The expression has to match the whole term.
+        let results = RegexpQuery::new("fu".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn invalid_regexp_fails() {
+        let inv_index = init_inv_index_from_texts(&["future"]);
+
+        assert!(RegexpQuery::new("fut(".to_string())
+            .matches(&inv_index.reader())
+            .is_err());
+    }
+
+    #[test]
+    fn can_cap_expansions() {
+        let inv_index = init_inv_index_from_texts(&["future", "futile", "fun"]);
+
+        assert!(RegexpQuery::new("f.*".to_string())
+            .with_max_expansions(2)
+            .matches(&inv_index.reader())
+            .is_err());
+    }
+}
@@ -0,0 +1,656 @@
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// A range of positions in a document, from `start` up to but not including `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Span {
+    pub start: u32,
+    pub end: u32,
+
+    // How many positions inside the span are not covered by the terms that matched.
+    pub width: u32,
+}
+
+/// Maps the ID's of matching documents to their spans, sorted by position.
+type Spans = BTreeMap<u32, Vec<Span>>;
+
+/// Positional queries, that match ranges of positions (spans) rather than whole documents.
+/// Span queries nest, so they can express things like "X within 5 words of Y, but not within
+/// 2 words of Z". Wrap a span query in `Query::Span` to use it as a clause of a boolean query.
+/// Like in Lucene, all terms of a span query have to be in the same field.
+/// # Example
+/// Documents where lawyer is within 5 words of contract, and judge is not within 2 words of it:
+/// ```
+/// use rucene::rucene_internal::query::SpanQuery;
+///
+/// let query = SpanQuery::not(
+///     SpanQuery::near(
+///         vec![
+///             SpanQuery::term("lawyer".to_string()),
+///             SpanQuery::term("contract".to_string()),
+///         ],
+///         5,
+///         false,
+///     ),
+///     SpanQuery::term("judge".to_string()),
+///     2,
+///     2,
+/// );
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum SpanQuery {
+    /// Matches every occurrence of the term in the field.
+    Term { field: String, term: String },
+    /// Matches spans where every clause matches, with at most `slop` unmatched positions between
+    /// them. With `in_order`, the clauses have to match in the given order without overlapping.
+    Near {
+        clauses: Vec<SpanQuery>,
+        slop: u32,
+        in_order: bool,
+    },
+    /// Matches the spans of any of the clauses.
+    Or(Vec<SpanQuery>),
+    /// Matches the spans of `include` that have no span of `exclude` within `pre` positions
+    /// before them or `post` positions after them.
+    Not {
+        include: Box<SpanQuery>,
+        exclude: Box<SpanQuery>,
+        pre: u32,
+        post: u32,
+    },
+    /// Matches the spans of the query that end at or before position `end`.
+    First { query: Box<SpanQuery>, end: u32 },
+}
+
+impl SpanQuery {
+    /// A query for the term in the default field.
+    pub fn term(term: String) -> Self {
+        SpanQuery::Term {
+            field: DEFAULT_FIELD.to_string(),
+            term,
+        }
+    }
+
+    pub fn near(clauses: Vec<SpanQuery>, slop: u32, in_order: bool) -> Self {
+        SpanQuery::Near {
+            clauses,
+            slop,
+            in_order,
+        }
+    }
+
+    pub fn or(clauses: Vec<SpanQuery>) -> Self {
+        SpanQuery::Or(clauses)
+    }
+
+    pub fn not(include: SpanQuery, exclude: SpanQuery, pre: u32, post: u32) -> Self {
+        SpanQuery::Not {
+            include: Box::new(include),
+            exclude: Box::new(exclude),
+            pre,
+            post,
+        }
+    }
+
+    pub fn first(query: SpanQuery, end: u32) -> Self {
+        SpanQuery::First {
+            query: Box::new(query),
+            end,
+        }
+    }
+
+    /// Moves every term of the query to the field.
+    pub fn with_field(mut self, field: String) -> Self {
+        self.set_field(&field);
+        self
+    }
+
+    fn set_field(&mut self, new_field: &str) {
+        match self {
+            SpanQuery::Term { field, .. } => *field = new_field.to_string(),
+            SpanQuery::Near { clauses, .. } | SpanQuery::Or(clauses) => clauses
+                .iter_mut()
+                .for_each(|clause| clause.set_field(new_field)),
+            SpanQuery::Not {
+                include, exclude, ..
+            } => {
+                include.set_field(new_field);
+                exclude.set_field(new_field);
+            }
+            SpanQuery::First { query, .. } => query.set_field(new_field),
+        }
+    }
+
+    /// Documents are scored by how many spans match in them, like phrases: wider spans count less.
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let mut fields = vec![];
+        self.collect_fields(&mut fields);
+        fields.sort();
+        fields.dedup();
+
+        let field = match fields.as_slice() {
+            [field] => *field,
+            [] => return Ok(DocScores::new()),
+            _ => {
+                return Err(format!(
+                    "Span queries must be on a single field, but are on {0}.",
+                    fields.join(", ")
+                )
+                .into())
+            }
+        };
+
+        let mut terms = vec![];
+        self.collect_terms(&mut terms);
+
+        let term_statistics = terms
+            .iter()
+            .map(|term| index.term_statistics(field, term))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(self
+            .spans(index)?
+            .into_iter()
+            .map(|(doc_id, spans)| {
+                let freq = spans
+                    .iter()
+                    .map(|span| 1.0 / (span.width as f32 + 1.0))
+                    .sum();
+
+                (doc_id, index.score(field, doc_id, freq, &term_statistics))
+            })
+            .collect())
+    }
+
+    /// The fields of all terms, including excluded ones.
+    fn collect_fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            SpanQuery::Term { field, .. } => fields.push(field),
+            SpanQuery::Near { clauses, .. } | SpanQuery::Or(clauses) => clauses
+                .iter()
+                .for_each(|clause| clause.collect_fields(fields)),
+            SpanQuery::Not {
+                include, exclude, ..
+            } => {
+                include.collect_fields(fields);
+                exclude.collect_fields(fields);
+            }
+            SpanQuery::First { query, .. } => query.collect_fields(fields),
+        }
+    }
+
+    /// The terms that contribute to the score. Excluded spans do not.
+    fn collect_terms<'a>(&'a self, terms: &mut Vec<&'a str>) {
+        match self {
+            SpanQuery::Term { term, .. } => terms.push(term),
+            SpanQuery::Near { clauses, .. } | SpanQuery::Or(clauses) => clauses
+                .iter()
+                .for_each(|clause| clause.collect_terms(terms)),
+            SpanQuery::Not { include, .. } => include.collect_terms(terms),
+            SpanQuery::First { query, .. } => query.collect_terms(terms),
+        }
+    }
+
+    pub(crate) fn spans(&self, index: &SegmentReader) -> Result<Spans, Box<dyn Error>> {
+        match self {
+            SpanQuery::Term { field, term } => Ok(index
+                .get_postings(field, term)?
+                .iter()
+                .map(|posting| {
+                    let spans = posting
+                        .positions
+                        .iter()
+                        .map(|position| Span {
+                            start: *position,
+                            end: position + 1,
+                            width: 0,
+                        })
+                        .collect();
+
+                    (posting.doc_id, spans)
+                })
+                .collect()),
+            SpanQuery::Near {
+                clauses,
+                slop,
+                in_order,
+            } => Self::near_spans(clauses, *slop, *in_order, index),
+            SpanQuery::Or(clauses) => {
+                let mut results = Spans::new();
+
+                for clause in clauses {
+                    for (doc_id, spans) in clause.spans(index)? {
+                        results.entry(doc_id).or_default().extend(spans);
+                    }
+                }
+
+                for spans in results.values_mut() {
+                    spans.sort();
+                    spans.dedup();
+                }
+
+                Ok(results)
+            }
+            SpanQuery::Not {
+                include,
+                exclude,
+                pre,
+                post,
+            } => {
+                let excluded = exclude.spans(index)?;
+
+                Ok(include
+                    .spans(index)?
+                    .into_iter()
+                    .filter_map(|(doc_id, spans)| {
+                        let excluded_spans =
+                            excluded.get(&doc_id).map(Vec::as_slice).unwrap_or(&[]);
+
+                        let spans: Vec<Span> = spans
+                            .into_iter()
+                            .filter(|span| {
+                                let start = span.start.saturating_sub(*pre);
+                                let end = span.end.saturating_add(*post);
+
+                                !excluded_spans
+                                    .iter()
+                                    .any(|excluded| excluded.start < end && start < excluded.end)
+                            })
+                            .collect();
+
+                        (!spans.is_empty()).then_some((doc_id, spans))
+                    })
+                    .collect())
+            }
+            SpanQuery::First { query, end } => Ok(query
+                .spans(index)?
+                .into_iter()
+                .filter_map(|(doc_id, spans)| {
+                    let spans: Vec<Span> =
+                        spans.into_iter().filter(|span| span.end <= *end).collect();
+
+                    (!spans.is_empty()).then_some((doc_id, spans))
+                })
+                .collect()),
+        }
+    }
+
+    fn near_spans(
+        clauses: &[SpanQuery],
+        slop: u32,
+        in_order: bool,
+        index: &SegmentReader,
+    ) -> Result<Spans, Box<dyn Error>> {
+        let mut clause_spans = vec![];
+
+        for clause in clauses {
+            clause_spans.push(clause.spans(index)?);
+        }
+
+        let mut results = Spans::new();
+
+        let first_clause = match clause_spans.first() {
+            Some(spans) => spans,
+            None => return Ok(results),
+        };
+
+        // Only documents that match every clause can match.
+        for doc_id in first_clause.keys() {
+            let doc_spans: Option<Vec<&Vec<Span>>> =
+                clause_spans.iter().map(|spans| spans.get(doc_id)).collect();
+
+            if let Some(doc_spans) = doc_spans {
+                let mut matches = if in_order {
+                    ordered_near_matches(&doc_spans, slop)
+                } else {
+                    unordered_near_matches(&doc_spans, slop)
+                };
+
+                if !matches.is_empty() {
+                    matches.sort();
+                    matches.dedup();
+                    results.insert(*doc_id, matches);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Finds the spans of the clauses that follow each other in order, like Lucene's
+/// `NearSpansOrdered`. For every span of the first clause, each following clause takes its first
+/// span that starts after the previous one ends. Spans are sorted, so the spans that the following
+/// clauses take only move forward, and every span is visited once rather than every combination.
+fn ordered_near_matches(clause_spans: &[&Vec<Span>], slop: u32) -> Vec<Span> {
+    let mut matches = vec![];
+    let mut next = vec![0; clause_spans.len()];
+
+    for first in clause_spans[0] {
+        let mut chosen = vec![*first];
+
+        for (spans, next) in clause_spans.iter().zip(next.iter_mut()).skip(1) {
+            let previous_end = chosen[chosen.len() - 1].end;
+
+            while *next < spans.len() && spans[*next].start < previous_end {
+                *next += 1;
+            }
+
+            match spans.get(*next) {
+                Some(span) => chosen.push(*span),
+                // No later span of the first clause can be followed by this clause either.
+                None => return matches,
+            }
+        }
+
+        if let Some(span) = near_span(&chosen, slop) {
+            matches.push(span);
+        }
+    }
+
+    matches
+}
+
+/// Finds the spans of the clauses that are near each other in any order, like Lucene's
+/// `NearSpansUnordered`. Each clause is at one of its spans, and after checking whether they are
+/// near enough, the clause whose span starts first moves to its next span, until a clause has no
+/// spans left. This visits every span once rather than every combination.
+fn unordered_near_matches(clause_spans: &[&Vec<Span>], slop: u32) -> Vec<Span> {
+    let mut matches = vec![];
+    let mut current = vec![0; clause_spans.len()];
+
+    if clause_spans.iter().any(|spans| spans.is_empty()) {
+        return matches;
+    }
+
+    loop {
+        let chosen: Vec<Span> = clause_spans
+            .iter()
+            .zip(&current)
+            .map(|(spans, index)| spans[*index])
+            .collect();
+
+        if let Some(span) = near_span(&chosen, slop) {
+            matches.push(span);
+        }
+
+        let first = (0..chosen.len())
+            .min_by_key(|clause| chosen[*clause])
+            .unwrap_or(0);
+
+        current[first] += 1;
+
+        if current[first] == clause_spans[first].len() {
+            return matches;
+        }
+    }
+}
+
+/// Returns the span that covers the chosen spans, if at most `slop` positions between them are
+/// not covered by them.
+fn near_span(chosen: &[Span], slop: u32) -> Option<Span> {
+    let start = chosen.iter().map(|span| span.start).min().unwrap_or(0);
+    let end = chosen.iter().map(|span| span.end).max().unwrap_or(0);
+    let covered = chosen.iter().map(|span| span.end - span.start).sum();
+    let gaps = (end - start).saturating_sub(covered);
+
+    (gaps <= slop).then(|| Span {
+        start,
+        end,
+        width: gaps + chosen.iter().map(|span| span.width).sum::<u32>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_inv_index_from_texts;
+    use crate::rucene_internal::inverted_index::InvertedIndex;
+    use crate::rucene_internal::query::span::{ordered_near_matches, unordered_near_matches, Span};
+    use crate::rucene_internal::query::{BooleanQuery, Query, SpanQuery, TermQuery};
+
+    fn init_inv_index() -> InvertedIndex {
+        init_inv_index_from_texts(&[
+            "the lawyer read the contract to the judge",
+            "the contract was signed by a lawyer in a hurry",
+            "a judge and a lawyer argued about the contract",
+        ])
+    }
+
+    fn term(term: &str) -> SpanQuery {
+        SpanQuery::term(term.to_string())
+    }
+
+    fn doc_ids(query: &SpanQuery, inv_index: &InvertedIndex) -> Vec<u32> {
+        query
+            .matches(&inv_index.reader())
+            .unwrap()
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn can_match_span_term() {
+        let inv_index = init_inv_index();
+
+        let spans = term("the").spans(&inv_index.reader()).unwrap();
+
+        assert_eq!(
+            spans[&0],
+            vec![
+                Span {
+                    start: 0,
+                    end: 1,
+                    width: 0
+                },
+                Span {
+                    start: 3,
+                    end: 4,
+                    width: 0
+                },
+                Span {
+                    start: 6,
+                    end: 7,
+                    width: 0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn can_match_span_near() {
+        let inv_index = init_inv_index();
+
+        let query = SpanQuery::near(vec![term("lawyer"), term("contract")], 2, true);
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0]);
+
+        // Unordered, "contract ... lawyer" in document 1 is too far apart.
+        let query = SpanQuery::near(vec![term("lawyer"), term("contract")], 2, false);
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0]);
+
+        let query = SpanQuery::near(vec![term("lawyer"), term("contract")], 5, false);
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0, 1, 2]);
+
+        let spans = SpanQuery::near(vec![term("lawyer"), term("contract")], 2, true)
+            .spans(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(
+            spans[&0],
+            vec![Span {
+                start: 1,
+                end: 5,
+                width: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn span_near_visits_each_span_once() {
+        let text = vec!["lawyer"; 1000].join(" ");
+        let inv_index = init_inv_index_from_texts(&[text.as_str()]);
+        let clauses = || vec![term("lawyer"), term("lawyer"), term("lawyer")];
+
+        // Enumerating every combination would take a billion steps, and count as many matches,
+        // rather than one per span of the clauses.
+        let ordered = SpanQuery::near(clauses(), 1000, true)
+            .spans(&inv_index.reader())
+            .unwrap();
+        let unordered = SpanQuery::near(clauses(), 1000, false)
+            .spans(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(ordered[&0].len(), 998);
+        assert_eq!(unordered[&0].len(), 1999);
+    }
+
+    #[test]
+    fn span_near_finds_each_span() {
+        let spans: Vec<Span> = (0..5)
+            .map(|position| Span {
+                start: position,
+                end: position + 1,
+                width: 0,
+            })
+            .collect();
+        let clause_spans = vec![&spans, &spans, &spans];
+        let span = |start, end| Span {
+            start,
+            end,
+            width: 0,
+        };
+
+        // In order, each span of the first clause is followed by the next two.
+        assert_eq!(
+            ordered_near_matches(&clause_spans, 5),
+            vec![span(0, 3), span(1, 4), span(2, 5)]
+        );
+
+        // In any order, the clauses move forward one at a time, and may share a position.
+        assert_eq!(
+            unordered_near_matches(&clause_spans, 5),
+            vec![
+                span(0, 1),
+                span(0, 2),
+                span(0, 2),
+                span(1, 2),
+                span(1, 3),
+                span(1, 3),
+                span(2, 3),
+                span(2, 4),
+                span(2, 4),
+                span(3, 4),
+                span(3, 5),
+                span(3, 5),
+                span(4, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_match_span_or() {
+        let inv_index = init_inv_index();
+
+        let query = SpanQuery::near(
+            vec![
+                SpanQuery::or(vec![term("read"), term("signed")]),
+                term("contract"),
+            ],
+            2,
+            false,
+        );
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0, 1]);
+    }
+
+    #[test]
+    fn can_match_span_not() {
+        let inv_index = init_inv_index();
+
+        // Lawyer within 5 words of contract, but not within 2 words of judge.
+        let query = SpanQuery::not(
+            SpanQuery::near(vec![term("lawyer"), term("contract")], 5, false),
+            term("judge"),
+            2,
+            2,
+        );
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0, 1, 2]);
+
+        // In document 0 judge comes 3 words after the span, and in document 2, 3 words before it.
+        let query = SpanQuery::not(
+            SpanQuery::near(vec![term("lawyer"), term("contract")], 5, false),
+            term("judge"),
+            3,
+            3,
+        );
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![1]);
+    }
+
+    #[test]
+    fn can_match_span_first() {
+        let inv_index = init_inv_index();
+
+        let query = SpanQuery::first(term("lawyer"), 2);
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0]);
+
+        let query = SpanQuery::first(term("lawyer"), 5);
+
+        assert_eq!(doc_ids(&query, &inv_index), vec![0, 2]);
+    }
+
+    #[test]
+    fn can_use_spans_in_boolean_query() {
+        let inv_index = init_inv_index();
+
+        let query = BooleanQuery::new(
+            vec![Query::from(SpanQuery::first(term("contract"), 5))],
+            vec![Query::from(TermQuery::new("judge".to_string()))],
+            vec![],
+        );
+
+        let results = query.matches(&inv_index.reader()).unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![1]);
+    }
+
+    #[test]
+    fn narrower_spans_score_higher() {
+        let inv_index =
+            init_inv_index_from_texts(&["lawyer contract", "lawyer wrote the contract"]);
+
+        let results = SpanQuery::near(vec![term("lawyer"), term("contract")], 5, true)
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results[&0] > results[&1]);
+    }
+
+    #[test]
+    fn spans_must_be_on_one_field() {
+        let inv_index = init_inv_index();
+
+        let query = SpanQuery::near(
+            vec![
+                term("lawyer"),
+                term("contract").with_field("title".to_string()),
+            ],
+            5,
+            false,
+        );
+
+        assert!(query.matches(&inv_index.reader()).is_err());
+
+        let query = query.with_field("title".to_string());
+
+        assert!(query.matches(&inv_index.reader()).unwrap().is_empty());
+    }
+}
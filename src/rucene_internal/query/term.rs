@@ -0,0 +1,85 @@
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use crate::rucene_internal::token::Token;
+use std::error::Error;
+
+/// Matches documents that contain the term in the field.
+#[derive(Debug, PartialEq)]
+pub struct TermQuery {
+    pub field: String,
+    pub term: String,
+}
+
+impl TermQuery {
+    /// A query for the term in the default field.
+    pub fn new(term: String) -> Self {
+        TermQuery {
+            field: DEFAULT_FIELD.to_string(),
+            term,
+        }
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let postings = index.get_postings(&self.field, &self.term)?;
+        let term_statistics = index.term_statistics(&self.field, &self.term)?;
+
+        Ok(postings
+            .iter()
+            .map(|posting| {
+                let freq = posting.term_freq() as f32;
+
+                (
+                    posting.doc_id,
+                    index.score(&self.field, posting.doc_id, freq, &[term_statistics]),
+                )
+            })
+            .collect())
+    }
+}
+
+impl From<Token> for TermQuery {
+    fn from(token: Token) -> Self {
+        TermQuery::new(token.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_test_inv_index;
+    use crate::rucene_internal::query::TermQuery;
+
+    #[test]
+    fn can_match_term() {
+        let inv_index = init_test_inv_index();
+
+        let results = TermQuery::new("future".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert_eq!(results.keys().copied().collect::<Vec<u32>>(), vec![0, 1]);
+
+        let results = TermQuery::new("present".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn can_match_term_in_field() {
+        let inv_index = init_test_inv_index();
+
+        let results = TermQuery::new("future".to_string())
+            .with_field("title".to_string())
+            .matches(&inv_index.reader())
+            .unwrap();
+
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::rucene_internal::query::multi_term::{
+    constant_score_matches, expand_terms, DEFAULT_MAX_EXPANSIONS,
+};
+use crate::rucene_internal::query::DocScores;
+use crate::rucene_internal::schema::DEFAULT_FIELD;
+use crate::rucene_internal::segment::SegmentReader;
+use regex::Regex;
+use std::error::Error;
+
+/// Matches documents that contain a term matching the pattern, where `*` matches any number of
+/// characters and `?` matches a single character, e.g. `f?t*re`. A backslash escapes the next
+/// character.
+///
+/// Only terms that start with the literal text before the first wildcard are checked, so patterns
+/// with a leading wildcard have to scan the whole term dictionary.
+#[derive(Debug, PartialEq)]
+pub struct WildcardQuery {
+    pub field: String,
+    pub pattern: String,
+    pub max_expansions: usize,
+}
+
+impl WildcardQuery {
+    pub fn new(pattern: String) -> Self {
+        WildcardQuery {
+            field: DEFAULT_FIELD.to_string(),
+            pattern,
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        }
+    }
+
+    pub fn with_field(mut self, field: String) -> Self {
+        self.field = field;
+        self
+    }
+
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = max_expansions;
+        self
+    }
+
+    pub(crate) fn matches(&self, index: &SegmentReader) -> Result<DocScores, Box<dyn Error>> {
+        let (prefix, regex) = self.compile()?;
+
+        let terms = expand_terms(
+            index
+                .terms_with_prefix(&self.field, &prefix)
+                .filter(|term| regex.is_match(term)),
+            self.max_expansions,
+        )?;
+
+        constant_score_matches(&self.field, &terms, index)
+    }
+
+    /// Returns the literal prefix of the pattern, and the pattern as a regular expression.
+    fn compile(&self) -> Result<(String, Regex), Box<dyn Error>> {
+        let mut prefix = String::new();
+        let mut in_prefix = true;
+        let mut regex = String::from("^(?s:");
+
+        let mut chars = self.pattern.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => {
+                    in_prefix = false;
+                    regex.push_str(".*");
+                }
+                '?' => {
+                    in_prefix = false;
+                    regex.push('.');
+                }
+                _ => {
+                    let literal = match c {
+                        '\\' => chars.next().unwrap_or('\\'),
+                        _ => c,
+                    };
+
+                    if in_prefix {
+                        prefix.push(literal);
+                    }
+
+                    regex.push_str(&regex::escape(&literal.to_string()));
+                }
+            }
+        }
+
+        regex.push_str(")$");
+
+        Ok((prefix, Regex::new(&regex)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::inverted_index::tests::init_inv_index_from_texts;
+    use crate::rucene_internal::query::WildcardQuery;
+
+    fn doc_ids(pattern: &str) -> Vec<u32> {
+        let inv_index =
+            init_inv_index_from_texts(&["future", "fixture", "fun", "refuture", "f*n", "fütüre"]);
+
+        WildcardQuery::new(pattern.to_string())
+            .matches(&inv_index.reader())
+            .unwrap()
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn can_match_wildcard() {
+        assert_eq!(doc_ids("f?t*re"), vec![0, 5]);
+        assert_eq!(doc_ids("f*ture"), vec![0, 1]);
+        assert_eq!(doc_ids("*future"), vec![0, 3]);
+        assert_eq!(doc_ids("fu?"), vec![2]);
+        assert_eq!(doc_ids("fun"), vec![2]);
+    }
+
+    #[test]
+    fn can_escape_wildcards() {
+        assert_eq!(doc_ids("f\\*n"), vec![4]);
+        assert_eq!(doc_ids("f*n"), vec![2, 4]);
+    }
+
+    #[test]
+    fn can_cap_expansions() {
+        let inv_index = init_inv_index_from_texts(&["future", "fixture", "fun"]);
+
+        assert!(WildcardQuery::new("f*".to_string())
+            .with_max_expansions(2)
+            .matches(&inv_index.reader())
+            .is_err());
+    }
+}
@@ -0,0 +1,835 @@
+use crate::rucene_internal::query::{
+    BooleanQuery, BoostQuery, Fuzziness, FuzzyQuery, MatchQueryBuilder, PrefixQuery, Query,
+    RegexpQuery, TermRangeQuery, WildcardQuery,
+};
+use crate::rucene_internal::schema::{Field, Schema};
+use std::error::Error;
+use std::fmt;
+use std::ops::Bound;
+
+/// A syntax error in a query string. The position is the index of the character where the error
+/// was found.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl ParseError {
+    fn new(message: String, position: usize) -> Self {
+        ParseError { message, position }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{0} at position {1}.", self.message, self.position)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Occur {
+    Must,
+    MustNot,
+    Should,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Conjunction {
+    And,
+    Or,
+}
+
+/// Parses Lucene's classic query syntax, e.g. `+cat -dog black "black cat"~2 body:cat^2 fut*`:
+/// * `+term` and `-term` (or `NOT term`, `!term`) make a clause required or prohibited, and
+///   `AND` (`&&`) and `OR` (`||`) combine clauses. Without operators, clauses are optional.
+/// * `(...)` groups clauses into a nested boolean query.
+/// * `"..."` is a phrase, and `"..."~2` a phrase with a slop of 2.
+/// * `field:` searches a clause in a field of the schema rather than the default field, e.g.
+///   `title:cat` or `title:(black cat)`.
+/// * `^2` boosts a clause.
+/// * `fut*` is a prefix, `f?t*re` a wildcard, `/fut(ure)?/` a regular expression, and `futur~1`
+///   a fuzzy term with at most one edit (two if no number is given).
+/// * `[a TO b]` is an inclusive and `{a TO b}` an exclusive term range, `*` leaves a side open.
+/// * `\` escapes the next character.
+///
+/// Terms and phrases go through the analysis of their field. The terms of prefix,
+/// wildcard, fuzzy and range queries are only lowercased, since analysis could break them up.
+/// # Example
+/// ```
+/// use rucene::rucene_internal::query_parser::QueryParser;
+/// use rucene::rucene_internal::schema::{Field, Schema, DEFAULT_FIELD};
+///
+/// let schema = Schema::new().with_field(Field::new(DEFAULT_FIELD.to_string()));
+/// let parser = QueryParser::new(&schema, DEFAULT_FIELD.to_string());
+///
+/// assert!(parser.parse("+cat -dog \"black cat\"~2").is_ok());
+/// assert_eq!(parser.parse("cat (dog").unwrap_err().position, 4);
+/// ```
+pub struct QueryParser<'a> {
+    schema: &'a Schema,
+    default_field: String,
+}
+
+impl<'a> QueryParser<'a> {
+    pub fn new(schema: &'a Schema, default_field: String) -> Self {
+        QueryParser {
+            schema,
+            default_field,
+        }
+    }
+
+    pub fn parse(&self, query: &str) -> Result<Query, ParseError> {
+        let mut parser = Parser {
+            query_parser: self,
+            field: self.indexed_field(&self.default_field, 0)?,
+            chars: query.chars().collect(),
+            position: 0,
+            depth: 0,
+        };
+
+        let query = parser.parse_query()?;
+
+        match parser.peek() {
+            None => Ok(query),
+            Some(c) => Err(ParseError::new(
+                format!("Unexpected '{0}'", c),
+                parser.position,
+            )),
+        }
+    }
+
+    fn indexed_field(&self, name: &str, position: usize) -> Result<&'a Field, ParseError> {
+        match self.schema.field(name) {
+            Some(field) if field.indexed => Ok(field),
+            Some(_) => Err(ParseError::new(
+                format!("Field '{0}' is not indexed", name),
+                position,
+            )),
+            None => Err(ParseError::new(
+                format!("Unknown field '{0}'", name),
+                position,
+            )),
+        }
+    }
+}
+
+/// How deep groups can be nested, so that parsing a query, which recurses into groups, does not
+/// overflow the stack.
+const MAX_DEPTH: usize = 100;
+
+/// Characters that end a term, unless escaped.
+const SPECIAL_CHARS: &[char] = &['(', ')', ':', '^', '~', '"', '[', ']', '{', '}', '/'];
+
+struct Parser<'a> {
+    query_parser: &'a QueryParser<'a>,
+
+    // The field of the clause being parsed.
+    field: &'a Field,
+
+    chars: Vec<char>,
+    position: usize,
+
+    // How many groups the parser is in.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Parses clauses until the end of the query or a closing parenthesis.
+    fn parse_query(&mut self) -> Result<Query, ParseError> {
+        let mut clauses: Vec<(Occur, Query)> = vec![];
+
+        loop {
+            self.skip_whitespace();
+
+            if matches!(self.peek(), None | Some(')')) {
+                break;
+            }
+
+            let conjunction_position = self.position;
+            let conjunction = self.parse_conjunction();
+
+            if conjunction.is_some() && clauses.is_empty() {
+                return Err(ParseError::new(
+                    String::from("Expected a clause before the operator"),
+                    conjunction_position,
+                ));
+            }
+
+            self.skip_whitespace();
+            let modifier = self.parse_modifier();
+            self.skip_whitespace();
+
+            if matches!(self.peek(), None | Some(')')) {
+                return Err(ParseError::new(
+                    String::from("Expected a clause after the operator"),
+                    self.position,
+                ));
+            }
+
+            let clause = self.parse_clause()?;
+
+            // Like Lucene, `a AND b` makes both sides required.
+            if conjunction == Some(Conjunction::And) {
+                if let Some((occur @ Occur::Should, _)) = clauses.last_mut() {
+                    *occur = Occur::Must;
+                }
+            }
+
+            let occur = match (modifier, conjunction) {
+                (Some(occur), _) => occur,
+                (None, Some(Conjunction::And)) => Occur::Must,
+                (None, _) => Occur::Should,
+            };
+
+            // Clauses can analyse to nothing, e.g. if they only contain punctuation.
+            if let Some(clause) = clause {
+                clauses.push((occur, clause));
+            }
+        }
+
+        if clauses.len() == 1 && clauses[0].0 != Occur::MustNot {
+            return Ok(clauses.remove(0).1);
+        }
+
+        if clauses.is_empty() {
+            return Ok(Query::MatchNone);
+        }
+
+        let mut must = vec![];
+        let mut must_not = vec![];
+        let mut should = vec![];
+
+        for (occur, clause) in clauses {
+            match occur {
+                Occur::Must => must.push(clause),
+                Occur::MustNot => must_not.push(clause),
+                Occur::Should => should.push(clause),
+            }
+        }
+
+        Ok(Query::from(BooleanQuery::new(must, must_not, should)))
+    }
+
+    fn parse_conjunction(&mut self) -> Option<Conjunction> {
+        if self.consume_keyword("AND") || self.consume_symbol("&&") {
+            Some(Conjunction::And)
+        } else if self.consume_keyword("OR") || self.consume_symbol("||") {
+            Some(Conjunction::Or)
+        } else {
+            None
+        }
+    }
+
+    fn parse_modifier(&mut self) -> Option<Occur> {
+        if self.consume_symbol("+") {
+            Some(Occur::Must)
+        } else if self.consume_symbol("-")
+            || self.consume_symbol("!")
+            || self.consume_keyword("NOT")
+        {
+            Some(Occur::MustNot)
+        } else {
+            None
+        }
+    }
+
+    fn parse_clause(&mut self) -> Result<Option<Query>, ParseError> {
+        // A field applies to the clause only, including any clauses in its parentheses.
+        let outer_field = self.field;
+
+        if let Some(field) = self.parse_field()? {
+            self.field = field;
+        }
+
+        let clause = match self.peek() {
+            Some('(') => {
+                let start = self.position;
+
+                if self.depth == MAX_DEPTH {
+                    return Err(ParseError::new(
+                        format!("Groups can be nested at most {0} deep", MAX_DEPTH),
+                        start,
+                    ));
+                }
+
+                self.position += 1;
+                self.depth += 1;
+
+                let query = self.parse_query()?;
+
+                self.depth -= 1;
+
+                if !self.consume_symbol(")") {
+                    return Err(ParseError::new(
+                        String::from("Missing closing parenthesis"),
+                        start,
+                    ));
+                }
+
+                Some(query)
+            }
+            Some('"') => self.parse_phrase()?,
+            Some('/') => Some(self.parse_regexp()?),
+            Some('[') | Some('{') => Some(self.parse_range()?),
+            _ => self.parse_term()?,
+        };
+
+        self.field = outer_field;
+
+        self.parse_boost(clause)
+    }
+
+    /// Consumes a `field:` prefix, if there is one. The field has to be an indexed field of the
+    /// schema.
+    fn parse_field(&mut self) -> Result<Option<&'a Field>, ParseError> {
+        let start = self.position;
+        let (field, _) = self.read_word(SPECIAL_CHARS);
+
+        if field.is_empty() || !self.consume_symbol(":") {
+            self.position = start;
+            return Ok(None);
+        }
+
+        self.query_parser.indexed_field(&field, start).map(Some)
+    }
+
+    fn parse_term(&mut self) -> Result<Option<Query>, ParseError> {
+        let start = self.position;
+        let (text, raw) = self.read_word(SPECIAL_CHARS);
+
+        if text.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => ParseError::new(format!("Unexpected '{0}'", c), self.position),
+                None => ParseError::new(String::from("Expected a term"), self.position),
+            });
+        }
+
+        if self.consume_symbol("~") {
+            let distance = match self.peek() {
+                Some(c) if c.is_ascii_digit() => self.parse_number()? as u32,
+                _ => FuzzyQuery::MAX_DISTANCE,
+            };
+
+            if distance > FuzzyQuery::MAX_DISTANCE {
+                return Err(ParseError::new(
+                    format!("Fuzziness can be at most {0}", FuzzyQuery::MAX_DISTANCE),
+                    start,
+                ));
+            }
+
+            return Ok(Some(Query::from(
+                FuzzyQuery::new(text.to_lowercase())
+                    .with_field(self.field.name.clone())
+                    .with_fuzziness(Fuzziness::Distance(distance)),
+            )));
+        }
+
+        let wildcards: Vec<usize> = Self::unescaped_positions(&raw, &['*', '?']);
+
+        if wildcards == [raw.chars().count() - 1] && raw.ends_with('*') {
+            let prefix = &text[..text.len() - 1];
+            return Ok(Some(Query::from(
+                PrefixQuery::new(prefix.to_lowercase()).with_field(self.field.name.clone()),
+            )));
+        }
+
+        if !wildcards.is_empty() {
+            return Ok(Some(Query::from(
+                WildcardQuery::new(raw.to_lowercase()).with_field(self.field.name.clone()),
+            )));
+        }
+
+        // A term that analyses to several tokens, e.g. `wi-fi`, matches any of them.
+        Ok(match MatchQueryBuilder::new(self.field).build(&text) {
+            Query::MatchNone => None,
+            query => Some(query),
+        })
+    }
+
+    fn parse_phrase(&mut self) -> Result<Option<Query>, ParseError> {
+        let start = self.position;
+        self.position += 1;
+
+        let (text, _) = self.read_until(&['"'], false);
+
+        if !self.consume_symbol("\"") {
+            return Err(ParseError::new(
+                String::from("Missing closing quote"),
+                start,
+            ));
+        }
+
+        let slop = if self.consume_symbol("~") {
+            self.parse_number()? as u32
+        } else {
+            0
+        };
+
+        Ok(
+            match MatchQueryBuilder::new(self.field).build_phrase(&text, slop) {
+                Query::MatchNone => None,
+                query => Some(query),
+            },
+        )
+    }
+
+    fn parse_regexp(&mut self) -> Result<Query, ParseError> {
+        let start = self.position;
+        self.position += 1;
+
+        let (pattern, _) = self.read_until(&['/'], false);
+
+        if !self.consume_symbol("/") {
+            return Err(ParseError::new(
+                String::from("Missing closing slash"),
+                start,
+            ));
+        }
+
+        Ok(Query::from(
+            RegexpQuery::new(pattern).with_field(self.field.name.clone()),
+        ))
+    }
+
+    fn parse_range(&mut self) -> Result<Query, ParseError> {
+        let start = self.position;
+        let lower_inclusive = self.chars[self.position] == '[';
+        self.position += 1;
+
+        let range_end_chars = &[']', '}'];
+
+        self.skip_whitespace();
+        let lower = self.parse_range_bound(lower_inclusive, range_end_chars)?;
+
+        self.skip_whitespace();
+        if !self.consume_keyword("TO") {
+            return Err(ParseError::new(
+                String::from("Expected 'TO'"),
+                self.position,
+            ));
+        }
+
+        self.skip_whitespace();
+        let upper_position = self.position;
+        let (upper_text, _) = self.read_word(range_end_chars);
+
+        self.skip_whitespace();
+        let upper_inclusive = match self.peek() {
+            Some(']') => true,
+            Some('}') => false,
+            _ => {
+                return Err(ParseError::new(
+                    String::from("Missing closing bracket"),
+                    start,
+                ))
+            }
+        };
+        self.position += 1;
+
+        if upper_text.is_empty() {
+            return Err(ParseError::new(
+                String::from("Expected a term"),
+                upper_position,
+            ));
+        }
+
+        let upper = Self::to_bound(upper_text, upper_inclusive);
+
+        Ok(Query::from(
+            TermRangeQuery::new(lower, upper).with_field(self.field.name.clone()),
+        ))
+    }
+
+    fn parse_range_bound(
+        &mut self,
+        inclusive: bool,
+        range_end_chars: &[char],
+    ) -> Result<Bound<String>, ParseError> {
+        let position = self.position;
+        let (text, _) = self.read_word(range_end_chars);
+
+        if text.is_empty() {
+            return Err(ParseError::new(String::from("Expected a term"), position));
+        }
+
+        Ok(Self::to_bound(text, inclusive))
+    }
+
+    fn to_bound(text: String, inclusive: bool) -> Bound<String> {
+        match (text.as_str(), inclusive) {
+            ("*", _) => Bound::Unbounded,
+            (_, true) => Bound::Included(text.to_lowercase()),
+            (_, false) => Bound::Excluded(text.to_lowercase()),
+        }
+    }
+
+    fn parse_boost(&mut self, clause: Option<Query>) -> Result<Option<Query>, ParseError> {
+        if !self.consume_symbol("^") {
+            return Ok(clause);
+        }
+
+        let boost = self.parse_number()?;
+
+        Ok(clause.map(|clause| Query::from(BoostQuery::new(clause, boost))))
+    }
+
+    fn parse_number(&mut self) -> Result<f32, ParseError> {
+        let start = self.position;
+
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+
+        let number: String = self.chars[start..self.position].iter().collect();
+
+        number
+            .parse()
+            .map_err(|_| ParseError::new(String::from("Expected a number"), start))
+    }
+
+    /// Reads characters until whitespace or one of the terminators, returning the text with
+    /// escapes resolved, and the raw text.
+    fn read_word(&mut self, terminators: &[char]) -> (String, String) {
+        self.read_until(terminators, true)
+    }
+
+    /// Like `read_word`, but only stops at whitespace if asked to, e.g. not within phrases.
+    fn read_until(&mut self, terminators: &[char], stop_at_whitespace: bool) -> (String, String) {
+        let mut text = String::new();
+        let mut raw = String::new();
+
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                raw.push(c);
+                self.position += 1;
+
+                if let Some(escaped) = self.peek() {
+                    text.push(escaped);
+                    raw.push(escaped);
+                    self.position += 1;
+                }
+
+                continue;
+            }
+
+            if terminators.contains(&c) || (stop_at_whitespace && c.is_whitespace()) {
+                break;
+            }
+
+            text.push(c);
+            raw.push(c);
+            self.position += 1;
+        }
+
+        (text, raw)
+    }
+
+    /// Returns the character positions of the characters in `text` that are not escaped.
+    fn unescaped_positions(text: &str, chars: &[char]) -> Vec<usize> {
+        let mut positions = vec![];
+        let mut escaped = false;
+
+        for (position, c) in text.chars().enumerate() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if chars.contains(&c) {
+                positions.push(position);
+            }
+        }
+
+        positions
+    }
+
+    /// Consumes an operator keyword, such as `AND`, if it is a whole word.
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        let length = keyword.chars().count();
+        let end = self.position + length;
+
+        let is_keyword = end <= self.chars.len()
+            && self.chars[self.position..end]
+                .iter()
+                .copied()
+                .eq(keyword.chars())
+            && self
+                .chars
+                .get(end)
+                .is_none_or(|c| c.is_whitespace() || *c == '(' || *c == '"');
+
+        if is_keyword {
+            self.position = end;
+        }
+
+        is_keyword
+    }
+
+    fn consume_symbol(&mut self, symbol: &str) -> bool {
+        let length = symbol.chars().count();
+        let end = self.position + length;
+
+        let is_symbol = end <= self.chars.len()
+            && self.chars[self.position..end]
+                .iter()
+                .copied()
+                .eq(symbol.chars());
+
+        if is_symbol {
+            self.position = end;
+        }
+
+        is_symbol
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::query::{
+        BooleanQuery, BoostQuery, Fuzziness, FuzzyQuery, PhraseQuery, PrefixQuery, Query,
+        RegexpQuery, TermQuery, TermRangeQuery, WildcardQuery,
+    };
+    use crate::rucene_internal::query_parser::{ParseError, QueryParser};
+    use crate::rucene_internal::schema::{Field, Schema};
+    use std::ops::Bound;
+
+    fn parse(query: &str) -> Result<Query, ParseError> {
+        let schema = Schema::new()
+            .with_field(Field::new("title".to_string()))
+            .with_field(Field::new("body".to_string()))
+            .with_field(Field::new("url".to_string()).with_indexed(false));
+
+        QueryParser::new(&schema, String::from("body")).parse(query)
+    }
+
+    fn term(term: &str) -> Query {
+        Query::from(TermQuery::new(term.to_string()))
+    }
+
+    fn phrase(terms: &[&str]) -> PhraseQuery {
+        PhraseQuery::new(terms.iter().map(|term| term.to_string()).collect())
+    }
+
+    fn boolean(must: Vec<Query>, must_not: Vec<Query>, should: Vec<Query>) -> Query {
+        Query::from(BooleanQuery::new(must, must_not, should))
+    }
+
+    #[test]
+    fn can_parse_term() {
+        assert_eq!(parse("Cat"), Ok(term("cat")));
+        assert_eq!(parse("  body:cat "), Ok(term("cat")));
+        assert_eq!(parse(""), Ok(Query::MatchNone));
+    }
+
+    #[test]
+    fn can_parse_boolean() {
+        assert_eq!(
+            parse("+cat -dog black \"black cat\"~2 body:cat^2 fut*"),
+            Ok(boolean(
+                vec![term("cat")],
+                vec![term("dog")],
+                vec![
+                    term("black"),
+                    Query::from(phrase(&["black", "cat"]).with_slop(2)),
+                    Query::from(BoostQuery::new(term("cat"), 2.0)),
+                    Query::from(PrefixQuery::new("fut".to_string())),
+                ],
+            ))
+        );
+    }
+
+    #[test]
+    fn can_parse_operators() {
+        assert_eq!(
+            parse("cat AND dog OR mouse"),
+            Ok(boolean(
+                vec![term("cat"), term("dog")],
+                vec![],
+                vec![term("mouse")]
+            ))
+        );
+        assert_eq!(
+            parse("cat && !dog"),
+            Ok(boolean(vec![term("cat")], vec![term("dog")], vec![]))
+        );
+        assert_eq!(
+            parse("cat NOT dog"),
+            Ok(boolean(vec![], vec![term("dog")], vec![term("cat")]))
+        );
+        assert_eq!(
+            parse("-dog"),
+            Ok(boolean(vec![], vec![term("dog")], vec![]))
+        );
+    }
+
+    #[test]
+    fn can_parse_groups() {
+        assert_eq!(
+            parse("(cat OR dog) AND NOT (black && white)"),
+            Ok(boolean(
+                vec![boolean(vec![], vec![], vec![term("cat"), term("dog")])],
+                vec![boolean(vec![term("black"), term("white")], vec![], vec![])],
+                vec![],
+            ))
+        );
+        assert_eq!(
+            parse("(cat dog)^1.5"),
+            Ok(Query::from(BoostQuery::new(
+                boolean(vec![], vec![], vec![term("cat"), term("dog")]),
+                1.5
+            )))
+        );
+    }
+
+    #[test]
+    fn can_parse_multi_term_queries() {
+        assert_eq!(
+            parse("F?t*re"),
+            Ok(Query::from(WildcardQuery::new("f?t*re".to_string())))
+        );
+        assert_eq!(parse("fut\\*"), Ok(term("fut*")));
+        assert_eq!(
+            parse("/fut(ure)?/"),
+            Ok(Query::from(RegexpQuery::new("fut(ure)?".to_string())))
+        );
+        assert_eq!(
+            parse("fuuture~1"),
+            Ok(Query::from(
+                FuzzyQuery::new("fuuture".to_string()).with_fuzziness(Fuzziness::Distance(1))
+            ))
+        );
+        assert_eq!(
+            parse("fuuture~"),
+            Ok(Query::from(
+                FuzzyQuery::new("fuuture".to_string()).with_fuzziness(Fuzziness::Distance(2))
+            ))
+        );
+        assert_eq!(
+            parse("[Aardvark TO azure}"),
+            Ok(Query::from(TermRangeQuery::new(
+                Bound::Included("aardvark".to_string()),
+                Bound::Excluded("azure".to_string())
+            )))
+        );
+        assert_eq!(
+            parse("{* TO b]"),
+            Ok(Query::from(TermRangeQuery::new(
+                Bound::Unbounded,
+                Bound::Included("b".to_string())
+            )))
+        );
+    }
+
+    #[test]
+    fn can_parse_analysed_phrase() {
+        assert_eq!(
+            parse("\"<b>Back</b> to the future\""),
+            Ok(Query::from(phrase(&["back", "to", "the", "future"])))
+        );
+        assert_eq!(parse("\"Cat\""), Ok(term("cat")));
+        assert_eq!(parse("\"...\""), Ok(Query::MatchNone));
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        let error = |message: &str, position: usize| {
+            Err(ParseError {
+                message: message.to_string(),
+                position,
+            })
+        };
+
+        assert_eq!(parse("cat \"black"), error("Missing closing quote", 4));
+        assert_eq!(parse("cat (dog"), error("Missing closing parenthesis", 4));
+        assert_eq!(parse("cat)"), error("Unexpected ')'", 3));
+        assert_eq!(
+            parse("AND cat"),
+            error("Expected a clause before the operator", 0)
+        );
+        assert_eq!(
+            parse("cat AND"),
+            error("Expected a clause after the operator", 7)
+        );
+        assert_eq!(parse("cat author:cat"), error("Unknown field 'author'", 4));
+        assert_eq!(parse("url:cat"), error("Field 'url' is not indexed", 0));
+        assert_eq!(parse("cat^x"), error("Expected a number", 4));
+        assert_eq!(parse("[a b]"), error("Expected 'TO'", 3));
+        assert_eq!(parse("[a TO b"), error("Missing closing bracket", 0));
+        assert_eq!(parse("cat~3"), error("Fuzziness can be at most 2", 0));
+        assert_eq!(parse("cat /fut"), error("Missing closing slash", 4));
+
+        assert_eq!(
+            parse("cat \"black").unwrap_err().to_string(),
+            "Missing closing quote at position 4."
+        );
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = |depth: usize| format!("{0}cat{1}", "(".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(parse(&nested(100)), Ok(term("cat")));
+        assert_eq!(
+            parse(&nested(100_000)),
+            Err(ParseError {
+                message: "Groups can be nested at most 100 deep".to_string(),
+                position: 100,
+            })
+        );
+    }
+
+    #[test]
+    fn can_parse_fields() {
+        let title = |query: Query| match query {
+            Query::Term(query) => Query::from(query.with_field("title".to_string())),
+            Query::Phrase(query) => Query::from(query.with_field("title".to_string())),
+            Query::Prefix(query) => Query::from(query.with_field("title".to_string())),
+            query => query,
+        };
+
+        assert_eq!(
+            parse("title:(Back \"to the\" fut*) Future"),
+            Ok(boolean(
+                vec![],
+                vec![],
+                vec![
+                    boolean(
+                        vec![],
+                        vec![],
+                        vec![
+                            title(term("back")),
+                            title(Query::from(phrase(&["to", "the"]))),
+                            title(Query::from(PrefixQuery::new("fut".to_string()))),
+                        ]
+                    ),
+                    term("future"),
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_default_field_fails() {
+        let schema = Schema::default();
+
+        assert_eq!(
+            QueryParser::new(&schema, String::from("title")).parse("cat"),
+            Err(ParseError {
+                message: String::from("Unknown field 'title'"),
+                position: 0,
+            })
+        );
+    }
+}
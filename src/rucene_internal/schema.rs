@@ -0,0 +1,244 @@
+//! A schema names the fields of documents, e.g. a title and a body, and decides how each of them
+//! is analysed and whether it is indexed. Fields are searched separately, so `title:cat` only
+//! matches documents with cat in their title.
+//! # Example
+//! ```
+//! use rucene::rucene_internal::schema::{Field, Schema};
+//! use rucene::IndexWriter;
+//!
+//! let schema = Schema::new()
+//!     .with_field(Field::new("title".to_string()))
+//!     .with_field(Field::new("body".to_string()))
+//!     .with_field(Field::new("url".to_string()).with_indexed(false));
+//!
+//! let mut writer = IndexWriter::new().with_schema(schema);
+//! writer
+//!     .index_fields("0", &[("title", "Back to the Future"), ("url", "example.com")])
+//!     .unwrap();
+//! ```
+
+use crate::rucene_internal::analysis::Analyser;
+use crate::rucene_internal::document::{AnalysedDocument, StoredFields};
+use crate::rucene_internal::token::Tokens;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// The field that is indexed and searched when no field is given.
+pub const DEFAULT_FIELD: &str = "body";
+
+/// The stored field that holds the source of a document as the application gave it, e.g. its
+/// JSON, which can hold more than the fields of the schema.
+pub const SOURCE_FIELD: &str = "_source";
+
+/// A named field of documents, and how its text is handled.
+pub struct Field {
+    pub name: String,
+
+    // Analyses the text of the field, both when indexing and, unless there is a search analyser,
+    // when searching.
+    pub analyser: Analyser,
+
+    // Analyses search text instead of `analyser`, e.g. to expand synonyms only at search time.
+    pub search_analyser: Option<Analyser>,
+
+    // Whether the field can be searched. Fields that are not indexed are not analysed.
+    pub indexed: bool,
+
+    // Whether the original text of the field is kept.
+    pub stored: bool,
+}
+
+impl Field {
+    /// An indexed and stored field, analysed with the standard analyser.
+    pub fn new(name: String) -> Self {
+        Field {
+            name,
+            analyser: Analyser::standard(),
+            search_analyser: None,
+            indexed: true,
+            stored: true,
+        }
+    }
+
+    pub fn with_analyser(mut self, analyser: Analyser) -> Self {
+        self.analyser = analyser;
+        self
+    }
+
+    pub fn with_search_analyser(mut self, search_analyser: Analyser) -> Self {
+        self.search_analyser = Some(search_analyser);
+        self
+    }
+
+    pub fn with_indexed(mut self, indexed: bool) -> Self {
+        self.indexed = indexed;
+        self
+    }
+
+    pub fn with_stored(mut self, stored: bool) -> Self {
+        self.stored = stored;
+        self
+    }
+
+    /// Returns the analyser that search text for the field goes through.
+    pub fn analyser_for_search(&self) -> &Analyser {
+        self.search_analyser.as_ref().unwrap_or(&self.analyser)
+    }
+}
+
+/// The fields of the documents in an index.
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// A schema without fields.
+    pub fn new() -> Self {
+        Schema { fields: vec![] }
+    }
+
+    /// Adds the field, replacing any field with the same name.
+    pub fn with_field(mut self, field: Field) -> Self {
+        self.fields.retain(|existing| existing.name != field.name);
+        self.fields.push(field);
+        self
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter()
+    }
+
+    /// Analyses the text of each field with the field's analyser, and keeps the text of stored
+    /// fields. Fields that are not indexed are not analysed, and fields that are not in the schema
+    /// are an error.
+    pub fn analyse(
+        &self,
+        id: &str,
+        fields: &[(&str, &str)],
+    ) -> Result<AnalysedDocument, Box<dyn Error>> {
+        let mut analysed_fields: BTreeMap<String, Tokens> = BTreeMap::new();
+        let mut stored_fields = StoredFields::new();
+
+        for (name, text) in fields {
+            let field = self
+                .field(name)
+                .ok_or_else(|| format!("Field '{0}' is not in the schema.", name))?;
+
+            if field.indexed {
+                analysed_fields
+                    .entry(field.name.clone())
+                    .or_default()
+                    .extend(field.analyser.analyse_text(text));
+            }
+
+            // A field given more than once is stored as a single text, like it is analysed.
+            if field.stored {
+                stored_fields
+                    .entry(field.name.clone())
+                    .and_modify(|stored| {
+                        stored.push(' ');
+                        stored.push_str(text);
+                    })
+                    .or_insert_with(|| text.to_string());
+            }
+        }
+
+        Ok(
+            AnalysedDocument::from_fields(id.to_string(), analysed_fields)
+                .with_stored_fields(stored_fields),
+        )
+    }
+}
+
+/// A schema with only the default field.
+impl Default for Schema {
+    fn default() -> Self {
+        Schema::new().with_field(Field::new(DEFAULT_FIELD.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::schema::{Field, Schema, DEFAULT_FIELD};
+    use crate::rucene_internal::token::Token;
+    use std::collections::BTreeMap;
+
+    fn init_schema() -> Schema {
+        Schema::new()
+            .with_field(Field::new("title".to_string()))
+            .with_field(Field::new("body".to_string()))
+            .with_field(Field::new("url".to_string()).with_indexed(false))
+            .with_field(Field::new("notes".to_string()).with_stored(false))
+    }
+
+    #[test]
+    fn can_analyse_fields() {
+        let document = init_schema()
+            .analyse(
+                "0",
+                &[
+                    ("title", "Future Cop"),
+                    ("url", "example.com"),
+                    ("title", "Again"),
+                ],
+            )
+            .unwrap();
+
+        let expected = BTreeMap::from([(
+            "title".to_string(),
+            vec![
+                Token::new("future".to_string()),
+                Token::new("cop".to_string()),
+                Token::new("again".to_string()),
+            ],
+        )]);
+
+        assert_eq!(document.fields, expected);
+    }
+
+    #[test]
+    fn can_store_fields() {
+        let document = init_schema()
+            .analyse(
+                "0",
+                &[
+                    ("title", "Future Cop"),
+                    ("url", "example.com"),
+                    ("notes", "Unstored"),
+                    ("title", "Again"),
+                ],
+            )
+            .unwrap();
+
+        let expected = BTreeMap::from([
+            ("title".to_string(), "Future Cop Again".to_string()),
+            ("url".to_string(), "example.com".to_string()),
+        ]);
+
+        assert_eq!(document.stored_fields, expected);
+        assert!(document.fields.contains_key("notes"));
+    }
+
+    #[test]
+    fn unknown_field_fails() {
+        let result = init_schema().analyse("0", &[("author", "Doc Brown")]);
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Field 'author' is not in the schema."
+        );
+    }
+
+    #[test]
+    fn fields_are_replaced() {
+        let schema =
+            Schema::default().with_field(Field::new(DEFAULT_FIELD.to_string()).with_stored(false));
+
+        assert_eq!(schema.fields().count(), 1);
+        assert!(!schema.field(DEFAULT_FIELD).unwrap().stored);
+    }
+}
@@ -0,0 +1,1092 @@
+//! A segment is a part of the index with its own term dictionaries and postings, like in Lucene.
+//! New documents are added to a segment in memory, which is flushed once it is full, and never
+//! changes after that. Documents are numbered densely within their segment, in the order they are
+//! added. Deleted documents stay in a segment until it is merged with others.
+
+use crate::rucene_internal::automaton::Automaton;
+use crate::rucene_internal::document::{AnalysedDocument, StoredFields};
+use crate::rucene_internal::fst::Fst;
+use crate::rucene_internal::live_docs::LiveDocs;
+use crate::rucene_internal::postings::Posting;
+use crate::rucene_internal::similarity::{CollectionStatistics, Similarity, TermStatistics};
+use crate::rucene_internal::store::{DataInput, DataOutput, Directory};
+use crate::rucene_internal::term_dictionary::TermDictionary;
+use crate::rucene_internal::token::Offsets;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ops::Bound;
+
+// This is a BTree rather than a HashMap, since HashMaps are not ordered.
+type PostingsList = BTreeMap<u32, Vec<Posting>>;
+
+// The files of a segment, and the codecs they are written with. A change to the format of a file
+// must bump the version of its codec.
+const TERMS_EXTENSION: &str = "terms";
+const TERMS_CODEC: &str = "RuceneTerms";
+const POSTINGS_EXTENSION: &str = "postings";
+const POSTINGS_CODEC: &str = "RucenePostings";
+const DOCS_EXTENSION: &str = "docs";
+const DOCS_CODEC: &str = "RuceneDocs";
+const VERSION: u32 = 3;
+
+/// The terms and lengths of a single field. Each field has its own term dictionary, so the same
+/// term in two fields has two ordinals, and therefore two postings.
+#[derive(Default, Clone)]
+struct FieldIndex {
+    // Maps the terms of the field to ordinal numbers that uniquely identify a term in any field.
+    // It is an FST once the segment no longer changes.
+    term_dictionary: TermDictionary,
+
+    // Maps the numbers of the documents that have the field to its length in tokens.
+    doc_lengths: BTreeMap<u32, u32>,
+
+    // Sum of all lengths of the field, used for the average field length.
+    total_doc_length: u64,
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct Segment {
+    // Names the files of the segment. Segments in memory do not have a name until they are flushed.
+    pub name: String,
+
+    term_acc: u32,
+
+    // The ID of each document, indexed by document number.
+    ids: Vec<String>,
+
+    // Maps the ID's of documents to their latest number in the segment.
+    doc_numbers: BTreeMap<String, u32>,
+
+    // Maps field names to the terms and lengths of the field.
+    fields: BTreeMap<String, FieldIndex>,
+
+    // Maps term ID to the postings of the documents that contain the term, sorted by document
+    // number.
+    postings_list: PostingsList,
+
+    // The stored fields of each document, indexed by document number.
+    stored_fields: Vec<StoredFields>,
+}
+
+impl Segment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the document as the next document number, which is returned.
+    pub(crate) fn add(&mut self, document: AnalysedDocument) -> Result<u32, Box<dyn Error>> {
+        let doc = self.ids.len() as u32;
+
+        self.doc_numbers.insert(document.id.clone(), doc);
+        self.ids.push(document.id);
+        self.stored_fields.push(document.stored_fields);
+
+        for (field, tokens) in document.fields {
+            let field_index = self.fields.entry(field).or_default();
+
+            let length = tokens.len() as u32;
+            field_index.doc_lengths.insert(doc, length);
+            field_index.total_doc_length += length as u64;
+
+            // Collect all occurrences of a term first, so the document gets a single posting per
+            // term. Postings are kept in order of first occurrence, so term ordinals follow the
+            // token stream.
+            let mut postings: Vec<(String, Posting)> = vec![];
+            let mut posting_indices: BTreeMap<String, usize> = BTreeMap::new();
+
+            for (position, token) in tokens.into_iter().enumerate() {
+                let posting_index =
+                    *posting_indices
+                        .entry(token.value.clone())
+                        .or_insert_with(|| {
+                            postings.push((token.value.clone(), Posting::new(doc)));
+                            postings.len() - 1
+                        });
+
+                postings[posting_index]
+                    .1
+                    .add_occurrence(position as u32, token.offsets);
+            }
+
+            for (term, posting) in postings {
+                match field_index.term_dictionary.get(&term) {
+                    Some(val) => {
+                        // Term exists in the dictionary; a document containing the term has been indexed before.
+                        let postings = self.postings_list.get_mut(&val).ok_or_else(|| {
+                            format!(
+                                // If a term exist in the term dictionary, then it should exist in
+                                // the posting list as well, since we add it to the posting list when created.
+                                "Value {0} is in the term dictionary but not in the posting list.",
+                                val
+                            )
+                        })?;
+
+                        // The document has the highest number so far, so its posting comes last.
+                        postings.push(posting);
+                    }
+                    None => {
+                        // Term does not exist in the dictionary; a document containing the term has not
+                        // been indexed before.
+                        field_index.term_dictionary.insert(term, self.term_acc);
+
+                        self.postings_list.insert(self.term_acc, vec![posting]);
+                        self.term_acc += 1;
+                    }
+                };
+            }
+        }
+
+        Ok(doc)
+    }
+
+    /// Merges the live documents of the segments into a new segment, keeping their order. Also
+    /// returns, for each of the segments, the new number of each of its documents, or `None` for
+    /// deleted documents.
+    pub(crate) fn merge(
+        name: String,
+        segments: &[(&Segment, &LiveDocs)],
+    ) -> (Segment, Vec<Vec<Option<u32>>>) {
+        let mut merged = Segment {
+            name,
+            ..Segment::new()
+        };
+        let mut doc_maps = vec![];
+
+        for (segment, live_docs) in segments {
+            let mut doc_map = vec![];
+
+            for (doc, id) in segment.ids.iter().enumerate() {
+                if live_docs.get(doc as u32) {
+                    let new_doc = merged.ids.len() as u32;
+
+                    merged.doc_numbers.insert(id.clone(), new_doc);
+                    merged.ids.push(id.clone());
+                    merged
+                        .stored_fields
+                        .push(segment.stored_fields[doc].clone());
+                    doc_map.push(Some(new_doc));
+                } else {
+                    doc_map.push(None);
+                }
+            }
+
+            for (field, field_index) in &segment.fields {
+                let merged_field = merged.fields.entry(field.clone()).or_default();
+
+                for (doc, length) in &field_index.doc_lengths {
+                    if let Some(new_doc) = doc_map[*doc as usize] {
+                        merged_field.doc_lengths.insert(new_doc, *length);
+                        merged_field.total_doc_length += *length as u64;
+                    }
+                }
+
+                for (term, ordinal) in field_index.term_dictionary.iter() {
+                    // Documents of later segments get higher numbers, so their postings come last.
+                    let postings: Vec<Posting> = segment.postings_list[&ordinal]
+                        .iter()
+                        .filter_map(|posting| {
+                            doc_map[posting.doc_id as usize].map(|new_doc| Posting {
+                                doc_id: new_doc,
+                                ..posting.clone()
+                            })
+                        })
+                        .collect();
+
+                    if postings.is_empty() {
+                        continue;
+                    }
+
+                    let merged_ordinal = match merged_field.term_dictionary.get(&term) {
+                        Some(ordinal) => ordinal,
+                        None => {
+                            merged_field.term_dictionary.insert(term, merged.term_acc);
+                            merged.term_acc += 1;
+                            merged.term_acc - 1
+                        }
+                    };
+
+                    merged
+                        .postings_list
+                        .entry(merged_ordinal)
+                        .or_default()
+                        .extend(postings);
+                }
+            }
+
+            doc_maps.push(doc_map);
+        }
+
+        merged
+            .fields
+            .retain(|_, field_index| !field_index.doc_lengths.is_empty());
+        merged.finish();
+
+        (merged, doc_maps)
+    }
+
+    /// Builds the FSTs of the term dictionaries, once no more documents are added to the segment.
+    /// The terms are numbered again in order, like the offsets of their postings in a file, since
+    /// ascending outputs share more of the FST than numbers in the order the terms came in.
+    pub(crate) fn finish(&mut self) {
+        let mut postings_list = PostingsList::new();
+
+        for field_index in self.fields.values_mut() {
+            let term_dictionary: BTreeMap<String, u32> = field_index
+                .term_dictionary
+                .iter()
+                .map(|(term, ordinal)| {
+                    let postings = self.postings_list.remove(&ordinal).unwrap_or_default();
+                    let new_ordinal = postings_list.len() as u32;
+                    postings_list.insert(new_ordinal, postings);
+
+                    (term, new_ordinal)
+                })
+                .collect();
+
+            field_index.term_dictionary = TermDictionary::Map(term_dictionary);
+            field_index.term_dictionary.finish();
+        }
+
+        self.term_acc = postings_list.len() as u32;
+        self.postings_list = postings_list;
+    }
+
+    /// The number of documents in the segment, including deleted ones.
+    pub fn max_doc(&self) -> u32 {
+        self.ids.len() as u32
+    }
+
+    /// Returns the numbers of all documents in the segment, in ascending order.
+    pub(crate) fn docs(&self) -> impl Iterator<Item = u32> {
+        0..self.max_doc()
+    }
+
+    pub fn id(&self, doc: u32) -> &str {
+        &self.ids[doc as usize]
+    }
+
+    /// Returns the latest number of the document with the ID, whether it is deleted or not.
+    pub fn doc_number(&self, id: &str) -> Option<u32> {
+        self.doc_numbers.get(id).copied()
+    }
+
+    pub fn stored_fields(&self, doc: u32) -> &StoredFields {
+        &self.stored_fields[doc as usize]
+    }
+
+    /// An estimate of the bytes that the segment takes, which merge policies use as its size.
+    pub fn size_in_bytes(&self) -> u64 {
+        let terms: usize = self
+            .fields
+            .values()
+            .map(|field_index| field_index.term_dictionary.size_in_bytes())
+            .sum();
+        let postings: usize = self
+            .postings_list
+            .values()
+            .flatten()
+            .map(|posting| {
+                4 + posting.positions.len() * 4
+                    + posting.offsets.as_ref().map(Vec::len).unwrap_or(0) * 16
+            })
+            .sum();
+        let docs: usize = self
+            .ids
+            .iter()
+            .zip(&self.stored_fields)
+            .map(|(id, stored_fields)| {
+                id.len() + stored_fields.values().map(String::len).sum::<usize>()
+            })
+            .sum();
+
+        (terms + postings + docs) as u64
+    }
+
+    pub fn doc_length(&self, field: &str, doc: u32) -> u32 {
+        self.fields
+            .get(field)
+            .and_then(|field_index| field_index.doc_lengths.get(&doc).copied())
+            .unwrap_or(0)
+    }
+
+    /// Returns the statistics of the term in the field of this segment.
+    pub(crate) fn term_statistics(
+        &self,
+        field: &str,
+        term: &str,
+    ) -> Result<TermStatistics, Box<dyn Error>> {
+        let postings = self.get_postings(field, term)?;
+
+        Ok(TermStatistics {
+            doc_freq: postings.len() as u32,
+            total_term_freq: postings
+                .iter()
+                .map(|posting| posting.term_freq() as u64)
+                .sum(),
+        })
+    }
+
+    /// Like Lucene, the statistics only count the documents that have the field.
+    pub(crate) fn collection_statistics(&self, field: &str) -> CollectionStatistics {
+        match self.fields.get(field) {
+            Some(field_index) => CollectionStatistics {
+                doc_count: field_index.doc_lengths.len() as u32,
+                sum_total_term_freq: field_index.total_doc_length,
+            },
+            None => CollectionStatistics {
+                doc_count: 0,
+                sum_total_term_freq: 0,
+            },
+        }
+    }
+
+    /// Returns the names of the fields that any document of the segment has.
+    pub fn field_names(&self) -> impl Iterator<Item = &str> {
+        self.fields.keys().map(String::as_str)
+    }
+
+    /// Returns the sorted numbers of documents that contain the term in the field, without
+    /// duplicates.
+    #[cfg(test)]
+    pub(crate) fn get_docs_from_term(
+        &self,
+        field: &str,
+        term: &str,
+    ) -> Result<Vec<u32>, Box<dyn Error>> {
+        Ok(self
+            .get_postings(field, term)?
+            .iter()
+            .map(|posting| posting.doc_id)
+            .collect())
+    }
+
+    /// Returns the terms of the field that start with the prefix, in ascending order. Since the
+    /// term dictionary is sorted, this is a range scan rather than a scan of every term.
+    pub(crate) fn terms_with_prefix<'a>(
+        &'a self,
+        field: &str,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.terms_in_range(field, Bound::Included(prefix), Bound::Unbounded)
+            .take_while(move |term| term.starts_with(prefix))
+    }
+
+    /// Returns the terms of the field within the bounds, in ascending order.
+    pub(crate) fn terms_in_range<'a>(
+        &'a self,
+        field: &str,
+        lower: Bound<&'a str>,
+        upper: Bound<&'a str>,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.term_dictionary(field)
+            .into_iter()
+            .flat_map(move |term_dictionary| term_dictionary.range(lower, upper))
+            .map(|(term, _)| term)
+    }
+
+    /// Returns the terms of the field that start with the prefix and whose remainder is accepted
+    /// by the automaton, in ascending order.
+    pub(crate) fn intersect_terms<A: Automaton>(
+        &self,
+        field: &str,
+        prefix: &str,
+        automaton: &A,
+    ) -> Vec<(String, A::State)> {
+        match self.term_dictionary(field) {
+            Some(term_dictionary) => term_dictionary.intersect(prefix, automaton),
+            None => vec![],
+        }
+    }
+
+    /// Returns the postings of the term in the field, sorted by document number.
+    pub(crate) fn get_postings(
+        &self,
+        field: &str,
+        term: &str,
+    ) -> Result<&[Posting], Box<dyn Error>> {
+        match self
+            .term_dictionary(field)
+            .and_then(|terms| terms.get(term))
+        {
+            Some(id) => {
+                let postings = self.postings_list.get(&id).ok_or_else(|| {
+                    format!(
+                        // If a term exist in the term dictionary, then it should exist in
+                        // the posting list as well, since we add it to the posting list when created.
+                        "Value {0} is in the term dictionary but not in the posting list.",
+                        id
+                    )
+                })?;
+
+                Ok(postings)
+            }
+            // If the term does not exist in the dictionary, then it means that no document containing the term has been indexed.
+            None => Ok(&[]),
+        }
+    }
+
+    /// Returns the term dictionary of the field, if any document with the field has been indexed.
+    fn term_dictionary(&self, field: &str) -> Option<&TermDictionary> {
+        self.fields
+            .get(field)
+            .map(|field_index| &field_index.term_dictionary)
+    }
+
+    /// Returns the names of the files of the segment.
+    pub fn files(&self) -> Vec<String> {
+        [TERMS_EXTENSION, POSTINGS_EXTENSION, DOCS_EXTENSION]
+            .iter()
+            .map(|extension| file_name(&self.name, extension))
+            .collect()
+    }
+
+    /// Writes the files of the segment to the directory, unless they have been written before.
+    pub(crate) fn write(&self, directory: &Directory) -> Result<(), Box<dyn Error>> {
+        for (extension, bytes) in [
+            (TERMS_EXTENSION, Self::write_terms as fn(&Self) -> Vec<u8>),
+            (POSTINGS_EXTENSION, Self::write_postings),
+            (DOCS_EXTENSION, Self::write_docs),
+        ] {
+            let name = file_name(&self.name, extension);
+
+            if !directory.exists(&name) {
+                directory.write(&name, &bytes(self))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the segment with the name from the files in the directory.
+    pub(crate) fn read(directory: &Directory, name: &str) -> Result<Self, Box<dyn Error>> {
+        let mut segment = Segment {
+            name: name.to_string(),
+            ..Segment::new()
+        };
+
+        segment.read_terms(&directory.read(&file_name(name, TERMS_EXTENSION))?)?;
+        segment.read_postings(&directory.read(&file_name(name, POSTINGS_EXTENSION))?)?;
+        segment.read_docs(&directory.read(&file_name(name, DOCS_EXTENSION))?)?;
+
+        // The files are checked one by one, so also check that they belong together.
+        for (field, field_index) in &segment.fields {
+            for (term, ordinal) in field_index.term_dictionary.iter() {
+                if !segment.postings_list.contains_key(&ordinal) {
+                    return Err(
+                        format!("Term '{0}' of field '{1}' has no postings.", term, field).into(),
+                    );
+                }
+            }
+        }
+
+        Ok(segment)
+    }
+
+    /// The term dictionary of each field, as the nodes of its FST.
+    fn write_terms(&self) -> Vec<u8> {
+        let mut output = DataOutput::new(TERMS_CODEC, VERSION);
+
+        output.write_vint(self.term_acc as u64);
+        output.write_vint(self.fields.len() as u64);
+
+        for (field, field_index) in &self.fields {
+            let fst = field_index.term_dictionary.fst();
+            let (bytes, root) = fst.to_bytes();
+
+            output.write_string(field);
+            output.write_vint(fst.len() as u64);
+            output.write_vint(root as u64);
+            output.write_bytes(bytes);
+        }
+
+        output.finish()
+    }
+
+    fn read_terms(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        let name = file_name(&self.name, TERMS_EXTENSION);
+        let mut input = DataInput::new(&name, bytes, TERMS_CODEC, VERSION)?;
+
+        self.term_acc = input.read_vint_u32()?;
+
+        for _ in 0..input.read_vint()? {
+            let field_index = self.fields.entry(input.read_string()?).or_default();
+
+            let len = input.read_vint()? as usize;
+            let root = input.read_vint()? as usize;
+            let bytes = input.read_bytes()?;
+
+            if root >= bytes.len() {
+                return Err(format!("File '{0}' has an FST without a root.", name).into());
+            }
+
+            field_index.term_dictionary = TermDictionary::Fst(Fst::from_bytes(bytes, root, len));
+        }
+
+        input.finish()
+    }
+
+    /// The postings of each term. Document numbers and positions are ascending, so they are
+    /// written as the difference to the previous one, which is small and takes few bytes.
+    fn write_postings(&self) -> Vec<u8> {
+        let mut output = DataOutput::new(POSTINGS_CODEC, VERSION);
+
+        output.write_vint(self.postings_list.len() as u64);
+
+        for (ordinal, postings) in &self.postings_list {
+            output.write_vint(*ordinal as u64);
+            output.write_vint(postings.len() as u64);
+
+            let mut previous_doc = 0;
+
+            for posting in postings {
+                output.write_vint((posting.doc_id - previous_doc) as u64);
+                previous_doc = posting.doc_id;
+
+                output.write_vint(posting.positions.len() as u64);
+
+                let mut previous_position = 0;
+
+                for position in &posting.positions {
+                    output.write_vint((position - previous_position) as u64);
+                    previous_position = *position;
+                }
+
+                match &posting.offsets {
+                    Some(offsets) => {
+                        output.write_u8(1);
+
+                        for offset in offsets {
+                            output.write_vint(offset.start as u64);
+                            output.write_vint((offset.end - offset.start) as u64);
+                        }
+                    }
+                    None => output.write_u8(0),
+                }
+            }
+        }
+
+        output.finish()
+    }
+
+    fn read_postings(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        let name = file_name(&self.name, POSTINGS_EXTENSION);
+        let mut input = DataInput::new(&name, bytes, POSTINGS_CODEC, VERSION)?;
+
+        for _ in 0..input.read_vint()? {
+            let ordinal = input.read_vint_u32()?;
+            let mut postings = vec![];
+            let mut doc = 0;
+
+            for _ in 0..input.read_vint()? {
+                doc += input.read_vint_u32()?;
+
+                let mut positions = vec![];
+                let mut position = 0;
+
+                for _ in 0..input.read_vint()? {
+                    position += input.read_vint_u32()?;
+                    positions.push(position);
+                }
+
+                let offsets = match input.read_u8()? {
+                    0 => None,
+                    _ => {
+                        let mut offsets = vec![];
+
+                        for _ in 0..positions.len() {
+                            let start = input.read_vint()? as usize;
+                            let end = start + input.read_vint()? as usize;
+
+                            offsets.push(Offsets { start, end });
+                        }
+
+                        Some(offsets)
+                    }
+                };
+
+                postings.push(Posting {
+                    doc_id: doc,
+                    positions,
+                    offsets,
+                });
+            }
+
+            self.postings_list.insert(ordinal, postings);
+        }
+
+        input.finish()
+    }
+
+    /// The ID, field lengths and stored fields of each document.
+    fn write_docs(&self) -> Vec<u8> {
+        let mut output = DataOutput::new(DOCS_CODEC, VERSION);
+
+        output.write_vint(self.ids.len() as u64);
+        for (id, stored_fields) in self.ids.iter().zip(&self.stored_fields) {
+            output.write_string(id);
+            output.write_vint(stored_fields.len() as u64);
+
+            for (name, text) in stored_fields {
+                output.write_string(name);
+                output.write_string(text);
+            }
+        }
+
+        output.write_vint(self.fields.len() as u64);
+        for (field, field_index) in &self.fields {
+            output.write_string(field);
+            output.write_vint(field_index.doc_lengths.len() as u64);
+
+            for (doc, length) in &field_index.doc_lengths {
+                output.write_vint(*doc as u64);
+                output.write_vint(*length as u64);
+            }
+        }
+
+        output.finish()
+    }
+
+    fn read_docs(&mut self, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        let name = file_name(&self.name, DOCS_EXTENSION);
+        let mut input = DataInput::new(&name, bytes, DOCS_CODEC, VERSION)?;
+
+        for doc in 0..input.read_vint_u32()? {
+            let id = input.read_string()?;
+            let mut stored_fields = StoredFields::new();
+
+            for _ in 0..input.read_vint()? {
+                let name = input.read_string()?;
+                let text = input.read_string()?;
+
+                stored_fields.insert(name, text);
+            }
+
+            self.doc_numbers.insert(id.clone(), doc);
+            self.ids.push(id);
+            self.stored_fields.push(stored_fields);
+        }
+
+        for _ in 0..input.read_vint()? {
+            let field_index = self.fields.entry(input.read_string()?).or_default();
+
+            for _ in 0..input.read_vint()? {
+                let doc = input.read_vint_u32()?;
+                let length = input.read_vint_u32()?;
+
+                field_index.doc_lengths.insert(doc, length);
+                field_index.total_doc_length += length as u64;
+            }
+        }
+
+        input.finish()
+    }
+}
+
+/// A segment as queries see it. Scores use the statistics of all segments together, so that they
+/// are the same no matter which segment a document is in, and can be compared across segments.
+pub(crate) struct SegmentReader<'a> {
+    segment: &'a Segment,
+
+    // All segments of the index, including this one, for the term statistics.
+    segments: Vec<&'a Segment>,
+
+    // The collection statistics of each field across all segments.
+    collection_statistics: BTreeMap<String, CollectionStatistics>,
+
+    similarity: &'a dyn Similarity,
+}
+
+impl<'a> SegmentReader<'a> {
+    pub fn new(
+        segment: &'a Segment,
+        segments: &[&'a Segment],
+        similarity: &'a dyn Similarity,
+    ) -> Self {
+        let mut collection_statistics: BTreeMap<String, CollectionStatistics> = BTreeMap::new();
+
+        for segment in segments {
+            for field in segment.field_names() {
+                let statistics = segment.collection_statistics(field);
+                let total = collection_statistics.entry(field.to_string()).or_insert(
+                    CollectionStatistics {
+                        doc_count: 0,
+                        sum_total_term_freq: 0,
+                    },
+                );
+
+                total.doc_count += statistics.doc_count;
+                total.sum_total_term_freq += statistics.sum_total_term_freq;
+            }
+        }
+
+        SegmentReader {
+            segment,
+            segments: segments.to_vec(),
+            collection_statistics,
+            similarity,
+        }
+    }
+
+    /// Scores a document with the index's `Similarity`, given how many times the matched terms
+    /// occur in the field.
+    pub(crate) fn score(
+        &self,
+        field: &str,
+        doc_id: u32,
+        freq: f32,
+        terms: &[TermStatistics],
+    ) -> f32 {
+        let collection =
+            self.collection_statistics
+                .get(field)
+                .copied()
+                .unwrap_or(CollectionStatistics {
+                    doc_count: 0,
+                    sum_total_term_freq: 0,
+                });
+
+        self.similarity.score(
+            freq,
+            self.segment.doc_length(field, doc_id),
+            &collection,
+            terms,
+        )
+    }
+
+    /// Returns the statistics of the term in the field, across all segments. Like in Lucene,
+    /// these include deleted documents until their segment is merged.
+    pub(crate) fn term_statistics(
+        &self,
+        field: &str,
+        term: &str,
+    ) -> Result<TermStatistics, Box<dyn Error>> {
+        let mut total = TermStatistics {
+            doc_freq: 0,
+            total_term_freq: 0,
+        };
+
+        for segment in &self.segments {
+            let statistics = segment.term_statistics(field, term)?;
+
+            total.doc_freq += statistics.doc_freq;
+            total.total_term_freq += statistics.total_term_freq;
+        }
+
+        Ok(total)
+    }
+
+    /// Returns the numbers of all documents in the segment, including deleted ones.
+    pub(crate) fn docs(&self) -> impl Iterator<Item = u32> {
+        self.segment.docs()
+    }
+
+    pub(crate) fn get_postings(
+        &self,
+        field: &str,
+        term: &str,
+    ) -> Result<&'a [Posting], Box<dyn Error>> {
+        self.segment.get_postings(field, term)
+    }
+
+    pub(crate) fn terms_with_prefix(
+        &self,
+        field: &str,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.segment.terms_with_prefix(field, prefix)
+    }
+
+    pub(crate) fn terms_in_range(
+        &self,
+        field: &str,
+        lower: Bound<&'a str>,
+        upper: Bound<&'a str>,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.segment.terms_in_range(field, lower, upper)
+    }
+
+    pub(crate) fn intersect_terms<A: Automaton>(
+        &self,
+        field: &str,
+        prefix: &str,
+        automaton: &A,
+    ) -> Vec<(String, A::State)> {
+        self.segment.intersect_terms(field, prefix, automaton)
+    }
+}
+
+fn file_name(segment: &str, extension: &str) -> String {
+    format!("{0}.{1}", segment, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::automaton::LevenshteinAutomaton;
+    use crate::rucene_internal::document::AnalysedDocument;
+    use crate::rucene_internal::inverted_index::tests::init_test_segment;
+    use crate::rucene_internal::live_docs::LiveDocs;
+    use crate::rucene_internal::postings::Posting;
+    use crate::rucene_internal::schema::DEFAULT_FIELD;
+    use crate::rucene_internal::segment::Segment;
+    use crate::rucene_internal::store::tests::temp_dir;
+    use crate::rucene_internal::store::Directory;
+    use crate::rucene_internal::token::{Offsets, Token};
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::ops::Bound;
+
+    #[test]
+    fn can_index_term_dictionary() {
+        let segment = init_test_segment();
+
+        let expected_term_dict = BTreeMap::from([
+            (String::from("again"), 5),
+            (String::from("back"), 0),
+            (String::from("cop"), 4),
+            (String::from("future"), 3),
+            (String::from("the"), 2),
+            (String::from("to"), 1),
+        ]);
+
+        assert!(segment
+            .term_dictionary(DEFAULT_FIELD)
+            .unwrap()
+            .iter()
+            .eq(expected_term_dict));
+    }
+
+    #[test]
+    fn can_index_postings_list() {
+        let segment = init_test_segment();
+
+        // The test tokens do not have offsets.
+        let posting = |doc_id: u32, positions: Vec<u32>| Posting {
+            doc_id,
+            positions,
+            offsets: None,
+        };
+
+        let expected_posting_list = BTreeMap::from([
+            (5, vec![posting(2, vec![1])]),
+            (0, vec![posting(0, vec![0]), posting(2, vec![0])]),
+            (4, vec![posting(1, vec![1])]),
+            (3, vec![posting(0, vec![3]), posting(1, vec![0])]),
+            (2, vec![posting(0, vec![2])]),
+            (1, vec![posting(0, vec![1])]),
+        ]);
+
+        assert_eq!(expected_posting_list, segment.postings_list);
+    }
+
+    #[test]
+    fn can_index_repeated_terms() {
+        let mut segment = Segment::new();
+
+        segment
+            .add(AnalysedDocument::new(
+                "7".to_string(),
+                vec![
+                    Token::with_offsets("the".to_string(), 0, 3),
+                    Token::with_offsets("cat".to_string(), 4, 7),
+                    Token::with_offsets("saw".to_string(), 8, 11),
+                    Token::with_offsets("the".to_string(), 12, 15),
+                    Token::with_offsets("dog".to_string(), 16, 19),
+                ],
+            ))
+            .unwrap();
+
+        let postings = segment.get_postings(DEFAULT_FIELD, "the").unwrap();
+
+        assert_eq!(
+            postings,
+            &[Posting {
+                doc_id: 0,
+                positions: vec![0, 3],
+                offsets: Some(vec![
+                    Offsets { start: 0, end: 3 },
+                    Offsets { start: 12, end: 15 }
+                ]),
+            }]
+        );
+        assert_eq!(postings[0].term_freq(), 2);
+    }
+
+    #[test]
+    fn can_index_fields() {
+        let mut segment = Segment::new();
+
+        let tokens = |text: &str| {
+            text.split_whitespace()
+                .map(|word| Token::new(word.to_string()))
+                .collect::<Vec<Token>>()
+        };
+
+        for (id, title, body) in [
+            (0, "future", "back to the future"),
+            (1, "cop", "future cop"),
+        ] {
+            segment
+                .add(AnalysedDocument::from_fields(
+                    id.to_string(),
+                    BTreeMap::from([
+                        ("title".to_string(), tokens(title)),
+                        ("body".to_string(), tokens(body)),
+                    ]),
+                ))
+                .unwrap();
+        }
+
+        assert_eq!(
+            segment.get_docs_from_term("title", "future").unwrap(),
+            vec![0]
+        );
+        assert_eq!(
+            segment.get_docs_from_term("body", "future").unwrap(),
+            vec![0, 1]
+        );
+        assert!(segment
+            .get_docs_from_term("author", "future")
+            .unwrap()
+            .is_empty());
+
+        // The same term has a separate ordinal in each field.
+        assert_ne!(
+            segment.term_dictionary("title").unwrap().get("future"),
+            segment.term_dictionary("body").unwrap().get("future")
+        );
+
+        // Each field has its own lengths.
+        assert_eq!(
+            segment.collection_statistics("title").sum_total_term_freq,
+            2
+        );
+        assert_eq!(segment.collection_statistics("body").sum_total_term_freq, 6);
+        assert_eq!(segment.docs().collect::<Vec<u32>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn can_merge() {
+        let first = init_test_segment();
+
+        let mut second = Segment::new();
+        second
+            .add(AnalysedDocument::new(
+                "3".to_string(),
+                vec![
+                    Token::new("future".to_string()),
+                    Token::new("again".to_string()),
+                ],
+            ))
+            .unwrap();
+
+        let mut live_docs = LiveDocs::all(first.max_doc());
+        live_docs.clear(1);
+
+        let (merged, doc_maps) = Segment::merge(
+            "_2".to_string(),
+            &[(&first, &live_docs), (&second, &LiveDocs::all(1))],
+        );
+
+        // Deleted documents are dropped, and the others keep their order.
+        assert_eq!(doc_maps, vec![vec![Some(0), None, Some(1)], vec![Some(2)]]);
+        assert_eq!(merged.max_doc(), 3);
+        assert_eq!(merged.id(2), "3");
+        assert_eq!(merged.doc_number("2"), Some(1));
+        assert_eq!(merged.doc_number("1"), None);
+
+        assert_eq!(
+            merged.get_docs_from_term(DEFAULT_FIELD, "future").unwrap(),
+            vec![0, 2]
+        );
+        assert_eq!(
+            merged.get_docs_from_term(DEFAULT_FIELD, "again").unwrap(),
+            vec![1, 2]
+        );
+        assert!(merged
+            .term_dictionary(DEFAULT_FIELD)
+            .unwrap()
+            .get("cop")
+            .is_none());
+        assert_eq!(merged.doc_length(DEFAULT_FIELD, 2), 2);
+        assert_eq!(
+            merged
+                .collection_statistics(DEFAULT_FIELD)
+                .sum_total_term_freq,
+            8
+        );
+    }
+
+    #[test]
+    fn finished_segment_has_same_terms() {
+        let segment = init_test_segment();
+        let mut finished = segment.clone();
+        finished.finish();
+
+        let terms = |segment: &Segment| -> Vec<(String, Vec<u32>)> {
+            segment
+                .terms_in_range(DEFAULT_FIELD, Bound::Unbounded, Bound::Unbounded)
+                .map(|term| {
+                    let docs = segment.get_docs_from_term(DEFAULT_FIELD, &term).unwrap();
+                    (term, docs)
+                })
+                .collect()
+        };
+
+        assert_eq!(terms(&finished), terms(&segment));
+
+        // Ordinals follow the order of the terms.
+        assert_eq!(
+            finished
+                .term_dictionary(DEFAULT_FIELD)
+                .unwrap()
+                .iter()
+                .map(|(_, ordinal)| ordinal)
+                .collect::<Vec<u32>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+
+        assert!(finished
+            .terms_with_prefix(DEFAULT_FIELD, "t")
+            .eq(["the", "to"]));
+        assert!(finished
+            .terms_in_range(
+                DEFAULT_FIELD,
+                Bound::Excluded("back"),
+                Bound::Included("cop")
+            )
+            .eq(["cop"]));
+
+        let automaton = LevenshteinAutomaton::new("cap", 1, false);
+        assert_eq!(
+            finished.intersect_terms(DEFAULT_FIELD, "", &automaton)[0].0,
+            "cop"
+        );
+    }
+
+    #[test]
+    fn can_write_and_read() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let mut segment = init_test_segment();
+        segment.name = "_0".to_string();
+        segment.write(&directory).unwrap();
+
+        assert_eq!(segment.files(), vec!["_0.terms", "_0.postings", "_0.docs"]);
+
+        let read = Segment::read(&directory, "_0").unwrap();
+
+        assert_eq!(read.ids, segment.ids);
+        assert_eq!(read.doc_numbers, segment.doc_numbers);
+        assert_eq!(read.postings_list, segment.postings_list);
+        assert_eq!(read.stored_fields, segment.stored_fields);
+        assert!(read
+            .term_dictionary(DEFAULT_FIELD)
+            .unwrap()
+            .iter()
+            .eq(segment.term_dictionary(DEFAULT_FIELD).unwrap().iter()));
+        assert_eq!(read.doc_length(DEFAULT_FIELD, 0), 4);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
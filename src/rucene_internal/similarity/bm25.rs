@@ -0,0 +1,107 @@
+use crate::rucene_internal::similarity::{CollectionStatistics, Similarity, TermStatistics};
+
+/// Okapi BM25, the default ranking function of Lucene.
+///
+/// `k1` controls how quickly the score saturates as a term repeats in a document, and `b` controls
+/// how much longer documents are penalised.
+#[derive(Debug, Clone, Copy)]
+pub struct BM25Similarity {
+    pub k1: f32,
+    pub b: f32,
+}
+
+impl BM25Similarity {
+    pub fn new(k1: f32, b: f32) -> Self {
+        BM25Similarity { k1, b }
+    }
+
+    /// Rare terms are worth more than common ones.
+    pub fn idf(&self, doc_count: u32, doc_freq: u32) -> f32 {
+        let doc_count = doc_count as f32;
+        let doc_freq = doc_freq as f32;
+
+        (1.0 + (doc_count - doc_freq + 0.5) / (doc_freq + 0.5)).ln()
+    }
+}
+
+impl Default for BM25Similarity {
+    /// The defaults used by Lucene.
+    fn default() -> Self {
+        BM25Similarity::new(1.2, 0.75)
+    }
+}
+
+impl Similarity for BM25Similarity {
+    fn score(
+        &self,
+        freq: f32,
+        doc_length: u32,
+        collection: &CollectionStatistics,
+        terms: &[TermStatistics],
+    ) -> f32 {
+        let idf: f32 = terms
+            .iter()
+            .map(|term| self.idf(collection.doc_count, term.doc_freq))
+            .sum();
+        let length_norm = 1.0 - self.b + self.b * doc_length as f32 / collection.avg_doc_length();
+
+        idf * freq / (freq + self.k1 * length_norm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::similarity::{
+        BM25Similarity, CollectionStatistics, Similarity, TermStatistics,
+    };
+
+    const COLLECTION: CollectionStatistics = CollectionStatistics {
+        doc_count: 10,
+        sum_total_term_freq: 100,
+    };
+
+    const TERM: TermStatistics = TermStatistics {
+        doc_freq: 2,
+        total_term_freq: 3,
+    };
+
+    #[test]
+    fn rare_terms_have_higher_idf() {
+        let bm25 = BM25Similarity::default();
+
+        assert!(bm25.idf(10, 1) > bm25.idf(10, 5));
+        assert!(bm25.idf(10, 10) > 0.0);
+    }
+
+    #[test]
+    fn shorter_documents_score_higher() {
+        let bm25 = BM25Similarity::default();
+
+        assert!(
+            bm25.score(1.0, 5, &COLLECTION, &[TERM]) > bm25.score(1.0, 20, &COLLECTION, &[TERM])
+        );
+    }
+
+    #[test]
+    fn term_frequency_saturates() {
+        let bm25 = BM25Similarity::default();
+
+        let once = bm25.score(1.0, 10, &COLLECTION, &[TERM]);
+        let twice = bm25.score(2.0, 10, &COLLECTION, &[TERM]);
+        let many = bm25.score(100.0, 10, &COLLECTION, &[TERM]);
+
+        assert!(twice > once);
+        assert!(many > twice);
+        assert!(many < bm25.idf(10, 2));
+    }
+
+    #[test]
+    fn can_disable_length_normalisation() {
+        let bm25 = BM25Similarity::new(1.2, 0.0);
+
+        assert_eq!(
+            bm25.score(1.0, 5, &COLLECTION, &[TERM]),
+            bm25.score(1.0, 20, &COLLECTION, &[TERM])
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use crate::rucene_internal::similarity::{CollectionStatistics, Similarity, TermStatistics};
+
+/// Scores every matching term with 1, regardless of statistics.
+/// Useful when only the number of matching clauses matters, e.g. for keyword-like fields.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BooleanSimilarity {}
+
+impl Similarity for BooleanSimilarity {
+    fn score(
+        &self,
+        _freq: f32,
+        _doc_length: u32,
+        _collection: &CollectionStatistics,
+        _terms: &[TermStatistics],
+    ) -> f32 {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::similarity::{
+        BooleanSimilarity, CollectionStatistics, Similarity, TermStatistics,
+    };
+
+    #[test]
+    fn scores_are_constant() {
+        let collection = CollectionStatistics {
+            doc_count: 10,
+            sum_total_term_freq: 100,
+        };
+        let term = TermStatistics {
+            doc_freq: 2,
+            total_term_freq: 3,
+        };
+
+        assert_eq!(
+            BooleanSimilarity {}.score(1.0, 5, &collection, &[term]),
+            1.0
+        );
+        assert_eq!(
+            BooleanSimilarity {}.score(3.0, 50, &collection, &[term]),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::rucene_internal::similarity::{CollectionStatistics, Similarity, TermStatistics};
+
+/// Lucene's classic TF-IDF scoring, the default before BM25.
+///
+/// The score grows with the square root of the term frequency and the square of the inverse
+/// document frequency, and is normalised by the square root of the document length.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassicSimilarity {}
+
+impl ClassicSimilarity {
+    pub fn idf(&self, doc_count: u32, doc_freq: u32) -> f32 {
+        1.0 + ((doc_count as f32 + 1.0) / (doc_freq as f32 + 1.0)).ln()
+    }
+}
+
+impl Similarity for ClassicSimilarity {
+    fn score(
+        &self,
+        freq: f32,
+        doc_length: u32,
+        collection: &CollectionStatistics,
+        terms: &[TermStatistics],
+    ) -> f32 {
+        let idf: f32 = terms
+            .iter()
+            .map(|term| self.idf(collection.doc_count, term.doc_freq))
+            .sum();
+        let length_norm = 1.0 / (doc_length.max(1) as f32).sqrt();
+
+        freq.sqrt() * idf * idf * length_norm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::similarity::{
+        ClassicSimilarity, CollectionStatistics, Similarity, TermStatistics,
+    };
+
+    const COLLECTION: CollectionStatistics = CollectionStatistics {
+        doc_count: 10,
+        sum_total_term_freq: 100,
+    };
+
+    #[test]
+    fn can_score_tf_idf() {
+        let classic = ClassicSimilarity {};
+        let term = TermStatistics {
+            doc_freq: 1,
+            total_term_freq: 4,
+        };
+
+        let idf = 1.0 + (11.0_f32 / 2.0).ln();
+
+        assert_eq!(
+            classic.score(4.0, 4, &COLLECTION, &[term]),
+            2.0 * idf * idf * 0.5
+        );
+    }
+
+    #[test]
+    fn term_frequency_does_not_saturate() {
+        let classic = ClassicSimilarity {};
+        let term = TermStatistics {
+            doc_freq: 1,
+            total_term_freq: 100,
+        };
+
+        assert_eq!(
+            classic.score(100.0, 4, &COLLECTION, &[term]),
+            10.0 * classic.score(1.0, 4, &COLLECTION, &[term])
+        );
+    }
+}
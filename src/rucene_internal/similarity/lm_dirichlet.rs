@@ -0,0 +1,83 @@
+use crate::rucene_internal::similarity::{CollectionStatistics, Similarity, TermStatistics};
+
+/// Language model with Bayesian smoothing using Dirichlet priors, from Zhai and Lafferty's
+/// "A Study of Smoothing Methods for Language Models Applied to Ad Hoc Information Retrieval".
+///
+/// `mu` controls how much the term's probability in the whole index smooths the probability in
+/// the document. Like Lucene, scores that would be negative are clamped to 0.
+#[derive(Debug, Clone, Copy)]
+pub struct LMDirichletSimilarity {
+    pub mu: f32,
+}
+
+impl LMDirichletSimilarity {
+    pub fn new(mu: f32) -> Self {
+        LMDirichletSimilarity { mu }
+    }
+}
+
+impl Default for LMDirichletSimilarity {
+    /// The default used by Lucene.
+    fn default() -> Self {
+        LMDirichletSimilarity::new(2000.0)
+    }
+}
+
+impl Similarity for LMDirichletSimilarity {
+    fn score(
+        &self,
+        freq: f32,
+        doc_length: u32,
+        collection: &CollectionStatistics,
+        terms: &[TermStatistics],
+    ) -> f32 {
+        let total_term_freq: u64 = terms.iter().map(|term| term.total_term_freq).sum();
+        let collection_probability =
+            (total_term_freq as f32 + 1.0) / (collection.sum_total_term_freq as f32 + 1.0);
+
+        let score = (1.0 + freq / (self.mu * collection_probability)).ln()
+            + (self.mu / (doc_length as f32 + self.mu)).ln();
+
+        score.max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::similarity::{
+        CollectionStatistics, LMDirichletSimilarity, Similarity, TermStatistics,
+    };
+
+    const COLLECTION: CollectionStatistics = CollectionStatistics {
+        doc_count: 10,
+        sum_total_term_freq: 1000,
+    };
+
+    #[test]
+    fn rare_terms_score_higher() {
+        let lm = LMDirichletSimilarity::new(10.0);
+        let rare = TermStatistics {
+            doc_freq: 1,
+            total_term_freq: 1,
+        };
+        let common = TermStatistics {
+            doc_freq: 8,
+            total_term_freq: 100,
+        };
+
+        assert!(
+            lm.score(1.0, 10, &COLLECTION, &[rare]) > lm.score(1.0, 10, &COLLECTION, &[common])
+        );
+    }
+
+    #[test]
+    fn scores_are_never_negative() {
+        let lm = LMDirichletSimilarity::default();
+        let common = TermStatistics {
+            doc_freq: 10,
+            total_term_freq: 900,
+        };
+
+        assert_eq!(lm.score(1.0, 100_000, &COLLECTION, &[common]), 0.0);
+    }
+}
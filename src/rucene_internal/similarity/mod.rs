@@ -0,0 +1,73 @@
+//! Similarities decide how documents are scored, and therefore ranked, for the terms they match.
+//! # Example
+//! A custom similarity that only counts occurrences:
+//! ```
+//! use rucene::rucene_internal::similarity::{CollectionStatistics, Similarity, TermStatistics};
+//! use rucene::IndexWriter;
+//!
+//! struct FrequencySimilarity {}
+//!
+//! impl Similarity for FrequencySimilarity {
+//!     fn score(
+//!         &self,
+//!         freq: f32,
+//!         _doc_length: u32,
+//!         _collection: &CollectionStatistics,
+//!         _terms: &[TermStatistics],
+//!     ) -> f32 {
+//!         freq
+//!     }
+//! }
+//!
+//! let writer = IndexWriter::with_similarity(Box::new(FrequencySimilarity {}));
+//! ```
+
+mod bm25;
+mod boolean;
+mod classic;
+mod lm_dirichlet;
+
+pub use bm25::BM25Similarity;
+pub use boolean::BooleanSimilarity;
+pub use classic::ClassicSimilarity;
+pub use lm_dirichlet::LMDirichletSimilarity;
+
+/// Statistics about the whole index.
+#[derive(Debug, Clone, Copy)]
+pub struct CollectionStatistics {
+    /// Number of documents in the index.
+    pub doc_count: u32,
+    /// Number of tokens in the index, i.e. the sum of all document lengths.
+    pub sum_total_term_freq: u64,
+}
+
+impl CollectionStatistics {
+    pub fn avg_doc_length(&self) -> f32 {
+        self.sum_total_term_freq as f32 / self.doc_count.max(1) as f32
+    }
+}
+
+/// Statistics about a single term in the whole index.
+#[derive(Debug, Clone, Copy)]
+pub struct TermStatistics {
+    /// Number of documents that contain the term.
+    pub doc_freq: u32,
+    /// Number of occurrences of the term in the index.
+    pub total_term_freq: u64,
+}
+
+pub trait Similarity
+where
+    Self: Send + Sync,
+{
+    /// Scores a document, given how many times (`freq`) the matched terms occur in it.
+    /// A single term query passes the statistics of one term, while queries that match several
+    /// terms together, such as phrases, pass the statistics of each of them.
+    fn score(
+        &self,
+        freq: f32,
+        doc_length: u32,
+        collection: &CollectionStatistics,
+        terms: &[TermStatistics],
+    ) -> f32;
+}
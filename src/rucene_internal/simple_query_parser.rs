@@ -0,0 +1,510 @@
+use crate::rucene_internal::query::{
+    BooleanQuery, Fuzziness, FuzzyQuery, MatchQueryBuilder, Operator, PrefixQuery, Query,
+};
+use crate::rucene_internal::schema::Field;
+
+/// Which operators the simple query parser understands. Characters of disabled operators are
+/// kept as text.
+#[derive(Debug, Clone, Copy)]
+pub struct SimpleQueryFlags {
+    /// `+term` makes a clause required.
+    pub and: bool,
+    /// `a | b` makes both clauses optional, even if the default operator is `And`.
+    pub or: bool,
+    /// `-term` makes a clause prohibited.
+    pub not: bool,
+    /// `"..."` is a phrase.
+    pub phrase: bool,
+    /// `term*` is a prefix.
+    pub prefix: bool,
+    /// `term~N` is a fuzzy term with at most `N` edits.
+    pub fuzzy: bool,
+    /// `"..."~N` is a phrase with a slop of `N`.
+    pub near: bool,
+}
+
+impl SimpleQueryFlags {
+    /// Parses flags in Elasticsearch's format, e.g. `AND|NOT|PHRASE`, `ALL` or `NONE`. Names are
+    /// case insensitive and unknown names are ignored.
+    pub fn parse(flags: &str) -> Self {
+        let mut parsed = SimpleQueryFlags::none();
+
+        for flag in flags.split('|') {
+            match flag.trim().to_uppercase().as_str() {
+                "ALL" => parsed = SimpleQueryFlags::default(),
+                "AND" => parsed.and = true,
+                "OR" => parsed.or = true,
+                "NOT" => parsed.not = true,
+                "PHRASE" => parsed.phrase = true,
+                "PREFIX" => parsed.prefix = true,
+                "FUZZY" => parsed.fuzzy = true,
+                "NEAR" | "SLOP" => parsed.near = true,
+                _ => {}
+            }
+        }
+
+        parsed
+    }
+
+    pub fn none() -> Self {
+        SimpleQueryFlags {
+            and: false,
+            or: false,
+            not: false,
+            phrase: false,
+            prefix: false,
+            fuzzy: false,
+            near: false,
+        }
+    }
+}
+
+impl Default for SimpleQueryFlags {
+    /// All operators are enabled.
+    fn default() -> Self {
+        SimpleQueryFlags {
+            and: true,
+            or: true,
+            not: true,
+            phrase: true,
+            prefix: true,
+            fuzzy: true,
+            near: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Occur {
+    Must,
+    MustNot,
+    Should,
+}
+
+/// A forgiving parser for search boxes, modelled after Lucene's `SimpleQueryParser`.
+///
+/// It understands a limited set of operators (`+ - | " * ~`, see `SimpleQueryFlags`), and never
+/// fails: unbalanced quotes and stray operators are ignored, so any input gives a query.
+/// All clauses search the same field.
+pub struct SimpleQueryParser<'a> {
+    field: &'a Field,
+    flags: SimpleQueryFlags,
+    default_operator: Operator,
+}
+
+impl<'a> SimpleQueryParser<'a> {
+    pub fn new(field: &'a Field) -> Self {
+        SimpleQueryParser {
+            field,
+            flags: SimpleQueryFlags::default(),
+            default_operator: Operator::Or,
+        }
+    }
+
+    pub fn with_flags(mut self, flags: SimpleQueryFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn with_default_operator(mut self, default_operator: Operator) -> Self {
+        self.default_operator = default_operator;
+        self
+    }
+
+    pub fn parse(&self, query: &str) -> Query {
+        let chars: Vec<char> = query.chars().collect();
+        let mut position = 0;
+
+        let mut clauses: Vec<(Occur, Query)> = vec![];
+        let mut modifier: Option<Occur> = None;
+        let mut after_or = false;
+
+        let default_occur = match self.default_operator {
+            Operator::Or => Occur::Should,
+            Operator::And => Occur::Must,
+        };
+
+        while position < chars.len() {
+            let c = chars[position];
+
+            if c.is_whitespace() {
+                position += 1;
+                continue;
+            }
+
+            if self.flags.or && c == '|' {
+                position += 1;
+                after_or = !clauses.is_empty();
+                continue;
+            }
+
+            if (self.flags.and && c == '+') || (self.flags.not && c == '-') {
+                position += 1;
+                modifier = Some(if c == '+' {
+                    Occur::Must
+                } else {
+                    Occur::MustNot
+                });
+                continue;
+            }
+
+            let clause = if self.flags.phrase && c == '"' {
+                match chars[position + 1..].iter().position(|c| *c == '"') {
+                    Some(length) => {
+                        let text: String =
+                            chars[position + 1..position + 1 + length].iter().collect();
+                        position += length + 2;
+
+                        let slop = self.read_tilde_number(&chars, &mut position, self.flags.near);
+                        self.phrase_clause(&text, slop.unwrap_or(0))
+                    }
+                    None => {
+                        // An unbalanced quote is ignored.
+                        position += 1;
+                        continue;
+                    }
+                }
+            } else {
+                let start = position;
+
+                while position < chars.len() && !self.ends_term(chars[position]) {
+                    position += 1;
+                }
+
+                let text: String = chars[start..position].iter().collect();
+                self.term_clause(&text)
+            };
+
+            if let Some(clause) = clause {
+                let occur = modifier.unwrap_or(default_occur);
+
+                // `a | b` makes both sides optional, unless they are prohibited.
+                if after_or {
+                    if let Some((previous @ Occur::Must, _)) = clauses.last_mut() {
+                        *previous = Occur::Should;
+                    }
+                }
+
+                let occur = if after_or && occur == Occur::Must && modifier.is_none() {
+                    Occur::Should
+                } else {
+                    occur
+                };
+
+                clauses.push((occur, clause));
+            }
+
+            modifier = None;
+            after_or = false;
+        }
+
+        Self::combine(clauses)
+    }
+
+    fn ends_term(&self, c: char) -> bool {
+        c.is_whitespace() || (self.flags.phrase && c == '"') || (self.flags.or && c == '|')
+    }
+
+    /// Reads `~N` at the position, if the operator is enabled. A `~` without a number is
+    /// consumed, and gives no number.
+    fn read_tilde_number(
+        &self,
+        chars: &[char],
+        position: &mut usize,
+        enabled: bool,
+    ) -> Option<u32> {
+        if !enabled || chars.get(*position) != Some(&'~') {
+            return None;
+        }
+
+        *position += 1;
+        let start = *position;
+
+        while *position < chars.len() && chars[*position].is_ascii_digit() {
+            *position += 1;
+        }
+
+        let number: String = chars[start..*position].iter().collect();
+
+        // Numbers that are too large are clamped rather than rejected.
+        number
+            .parse::<u64>()
+            .ok()
+            .map(|number| number.min(u32::MAX as u64) as u32)
+    }
+
+    fn term_clause(&self, text: &str) -> Option<Query> {
+        let chars: Vec<char> = text.chars().collect();
+
+        if self.flags.fuzzy {
+            if let Some(tilde) = chars.iter().rposition(|c| *c == '~') {
+                let term: String = chars[..tilde].iter().collect();
+                let mut position = tilde;
+                let distance = self.read_tilde_number(&chars, &mut position, true);
+
+                if position == chars.len() {
+                    // A `~` without a term is a stray operator.
+                    if term.is_empty() {
+                        return None;
+                    }
+
+                    let fuzziness = match distance {
+                        Some(distance) => {
+                            Fuzziness::Distance(distance.min(FuzzyQuery::MAX_DISTANCE))
+                        }
+                        None => Fuzziness::Auto,
+                    };
+
+                    return Some(Query::from(
+                        FuzzyQuery::new(term.to_lowercase())
+                            .with_field(self.field.name.clone())
+                            .with_fuzziness(fuzziness),
+                    ));
+                }
+            }
+        }
+
+        if self.flags.prefix && text.ends_with('*') {
+            let prefix = text.trim_end_matches('*');
+
+            // A lone `*` is a stray operator. A short prefix may have too many terms, which only
+            // searches the first ones rather than failing.
+            return (!prefix.is_empty()).then(|| {
+                Query::from(
+                    PrefixQuery::new(prefix.to_lowercase())
+                        .with_field(self.field.name.clone())
+                        .with_truncation(true),
+                )
+            });
+        }
+
+        // A term that analyses to several tokens, e.g. `wi-fi`, matches any of them.
+        match MatchQueryBuilder::new(self.field).build(text) {
+            Query::MatchNone => None,
+            query => Some(query),
+        }
+    }
+
+    fn phrase_clause(&self, text: &str, slop: u32) -> Option<Query> {
+        match MatchQueryBuilder::new(self.field).build_phrase(text, slop) {
+            Query::MatchNone => None,
+            query => Some(query),
+        }
+    }
+
+    fn combine(mut clauses: Vec<(Occur, Query)>) -> Query {
+        if clauses.is_empty() {
+            return Query::MatchNone;
+        }
+
+        if clauses.len() == 1 && clauses[0].0 != Occur::MustNot {
+            return clauses.remove(0).1;
+        }
+
+        let mut must = vec![];
+        let mut must_not = vec![];
+        let mut should = vec![];
+
+        for (occur, clause) in clauses {
+            match occur {
+                Occur::Must => must.push(clause),
+                Occur::MustNot => must_not.push(clause),
+                Occur::Should => should.push(clause),
+            }
+        }
+
+        // Unlike in Lucene, only prohibited clauses should not match nothing in a search box.
+        if must.is_empty() && should.is_empty() {
+            must.push(Query::MatchAll);
+        }
+
+        Query::from(BooleanQuery::new(must, must_not, should))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rucene_internal::query::{
+        BooleanQuery, Fuzziness, FuzzyQuery, Operator, PhraseQuery, PrefixQuery, Query, TermQuery,
+        DEFAULT_MAX_EXPANSIONS,
+    };
+    use crate::rucene_internal::schema::Field;
+    use crate::rucene_internal::simple_query_parser::{SimpleQueryFlags, SimpleQueryParser};
+    use crate::IndexWriter;
+
+    fn body() -> Field {
+        Field::new("body".to_string())
+    }
+
+    fn parse(query: &str) -> Query {
+        SimpleQueryParser::new(&body()).parse(query)
+    }
+
+    fn term(term: &str) -> Query {
+        Query::from(TermQuery::new(term.to_string()))
+    }
+
+    fn boolean(must: Vec<Query>, must_not: Vec<Query>, should: Vec<Query>) -> Query {
+        Query::from(BooleanQuery::new(must, must_not, should))
+    }
+
+    #[test]
+    fn can_parse_terms() {
+        assert_eq!(parse("Cat"), term("cat"));
+        assert_eq!(parse("   "), Query::MatchNone);
+        assert_eq!(
+            parse("black cat"),
+            boolean(vec![], vec![], vec![term("black"), term("cat")])
+        );
+    }
+
+    #[test]
+    fn can_parse_operators() {
+        assert_eq!(
+            parse("+cat -dog \"black cat\"~2 fut* futur~1"),
+            boolean(
+                vec![term("cat")],
+                vec![term("dog")],
+                vec![
+                    Query::from(
+                        PhraseQuery::new(vec!["black".to_string(), "cat".to_string()]).with_slop(2)
+                    ),
+                    Query::from(PrefixQuery::new("fut".to_string()).with_truncation(true)),
+                    Query::from(
+                        FuzzyQuery::new("futur".to_string()).with_fuzziness(Fuzziness::Distance(1))
+                    ),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn or_overrides_default_and() {
+        let field = body();
+        let parser = SimpleQueryParser::new(&field).with_default_operator(Operator::And);
+
+        assert_eq!(
+            parser.parse("black cat"),
+            boolean(vec![term("black"), term("cat")], vec![], vec![])
+        );
+        assert_eq!(
+            parser.parse("black cat | dog"),
+            boolean(vec![term("black")], vec![], vec![term("cat"), term("dog")])
+        );
+    }
+
+    #[test]
+    fn only_prohibited_clauses_match_all() {
+        assert_eq!(
+            parse("-dog"),
+            boolean(vec![Query::MatchAll], vec![term("dog")], vec![])
+        );
+    }
+
+    #[test]
+    fn never_fails_on_malformed_input() {
+        assert_eq!(
+            parse("\"black cat"),
+            boolean(vec![], vec![], vec![term("black"), term("cat")])
+        );
+        assert_eq!(parse("cat +"), term("cat"));
+        assert_eq!(parse("| cat -"), term("cat"));
+        assert_eq!(parse("+-|* ~ \""), Query::MatchNone);
+        assert_eq!(parse("\"\""), Query::MatchNone);
+        assert_eq!(
+            parse("futur~9 cat~"),
+            boolean(
+                vec![],
+                vec![],
+                vec![
+                    Query::from(
+                        FuzzyQuery::new("futur".to_string()).with_fuzziness(Fuzziness::Distance(2))
+                    ),
+                    Query::from(FuzzyQuery::new("cat".to_string()).with_fuzziness(Fuzziness::Auto)),
+                ]
+            )
+        );
+        assert_eq!(
+            parse("\"black cat\"~99999999999"),
+            Query::from(
+                PhraseQuery::new(vec!["black".to_string(), "cat".to_string()]).with_slop(u32::MAX)
+            )
+        );
+    }
+
+    #[test]
+    fn can_parse_flags() {
+        let flags = SimpleQueryFlags::parse("and|Phrase|unknown");
+
+        assert!(flags.and && flags.phrase);
+        assert!(!flags.or && !flags.not && !flags.prefix && !flags.fuzzy && !flags.near);
+
+        let flags = SimpleQueryFlags::parse("ALL");
+
+        assert!(flags.and && flags.or && flags.near);
+        assert!(!SimpleQueryFlags::parse("NONE").and);
+    }
+
+    #[test]
+    fn disabled_operators_are_text() {
+        let field = body();
+        let parser = SimpleQueryParser::new(&field).with_flags(SimpleQueryFlags::none());
+
+        assert_eq!(parser.parse("+cat"), term("+cat"));
+        assert_eq!(parser.parse("fut*"), term("fut*"));
+        assert_eq!(
+            parser.parse("\"black cat\""),
+            boolean(vec![], vec![], vec![term("\"black"), term("cat\"")])
+        );
+
+        let parser = SimpleQueryParser::new(&field).with_flags(SimpleQueryFlags {
+            near: false,
+            ..SimpleQueryFlags::default()
+        });
+
+        assert_eq!(
+            parser.parse("\"black cat\"~2"),
+            Query::from(PhraseQuery::new(vec![
+                "black".to_string(),
+                "cat".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn searches_the_field() {
+        let field = Field::new("title".to_string());
+
+        assert_eq!(
+            SimpleQueryParser::new(&field).parse("Cat fut*"),
+            boolean(
+                vec![],
+                vec![],
+                vec![
+                    Query::from(TermQuery::new("cat".to_string()).with_field("title".to_string())),
+                    Query::from(
+                        PrefixQuery::new("fut".to_string())
+                            .with_field("title".to_string())
+                            .with_truncation(true)
+                    ),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn prefix_with_too_many_terms_does_not_fail() {
+        // A single segment, since each segment searches the first terms of its own.
+        let mut writer = IndexWriter::new().with_max_buffered_docs(DEFAULT_MAX_EXPANSIONS + 1);
+        for i in 0..DEFAULT_MAX_EXPANSIONS + 1 {
+            writer
+                .index_text(&i.to_string(), &format!("future{0}", i))
+                .unwrap();
+        }
+        writer.refresh();
+
+        let results = writer.reader().search(parse("fut*")).unwrap();
+
+        assert_eq!(results.len(), DEFAULT_MAX_EXPANSIONS);
+    }
+}
@@ -0,0 +1,417 @@
+//! Files of an index on disk. Every file starts with a header of a magic number, the name of the
+//! codec that wrote it and the codec's version, and ends with a CRC-32 checksum of everything
+//! before it, like Lucene's codec headers and footers. Reading a file checks both, so corrupt
+//! files and files of another codec or version are an error rather than a wrong index.
+//!
+//! Files of segments are written once and never changed. A commit writes the new files first, and
+//! then replaces the `commit` file, which lists the segments of the index. Until the replacement
+//! the previous commit stays intact, so a crash while committing never leaves a half written
+//! index behind.
+
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// "RCNE", the first bytes of every file.
+const MAGIC: u32 = 0x52434e45;
+
+pub(crate) const COMMIT_FILE: &str = "commit";
+
+// Files of segments start with this, so that other files in the directory are left alone.
+const SEGMENT_FILE_PREFIX: char = '_';
+
+/// Builds the bytes of a file, starting with its header.
+pub(crate) struct DataOutput {
+    bytes: Vec<u8>,
+}
+
+impl DataOutput {
+    pub fn new(codec: &str, version: u32) -> Self {
+        let mut output = DataOutput { bytes: vec![] };
+
+        output.write_u32(MAGIC);
+        output.write_string(codec);
+        output.write_u32(version);
+
+        output
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    /// Writes a number in as few bytes as possible: seven bits per byte, and the high bit set on
+    /// all but the last byte.
+    pub fn write_vint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.write_u8((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+
+        self.write_u8(value as u8);
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        self.write_bytes(value.as_bytes());
+    }
+
+    /// Writes the bytes after their length.
+    pub fn write_bytes(&mut self, value: &[u8]) {
+        self.write_vint(value.len() as u64);
+        self.bytes.extend(value);
+    }
+
+    /// Appends the checksum, and returns the bytes of the file.
+    pub fn finish(mut self) -> Vec<u8> {
+        let checksum = crc32(&self.bytes);
+        self.write_u32(checksum);
+
+        self.bytes
+    }
+}
+
+/// Reads the bytes of a file, after checking its checksum and header.
+pub(crate) struct DataInput<'a> {
+    name: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> DataInput<'a> {
+    pub fn new(
+        name: &'a str,
+        bytes: &'a [u8],
+        codec: &str,
+        version: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        if bytes.len() < 4 {
+            return Err(format!("File '{0}' is truncated.", name).into());
+        }
+
+        let (content, footer) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes(footer.try_into()?);
+        let actual = crc32(content);
+
+        if actual != expected {
+            return Err(format!(
+                "File '{0}' is corrupt: its checksum is {1:08x}, but should be {2:08x}.",
+                name, actual, expected
+            )
+            .into());
+        }
+
+        let mut input = DataInput {
+            name,
+            bytes: content,
+            position: 0,
+        };
+
+        if input.read_u32()? != MAGIC {
+            return Err(format!("File '{0}' is not a Rucene file.", name).into());
+        }
+
+        let file_codec = input.read_string()?;
+        if file_codec != codec {
+            return Err(format!(
+                "File '{0}' has codec '{1}', but should have codec '{2}'.",
+                name, file_codec, codec
+            )
+            .into());
+        }
+
+        let file_version = input.read_u32()?;
+        if file_version != version {
+            return Err(format!(
+                "File '{0}' has version {1}, but only version {2} is supported.",
+                name, file_version, version
+            )
+            .into());
+        }
+
+        Ok(input)
+    }
+
+    fn read_exact(&mut self, length: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("File '{0}' ends unexpectedly.", self.name))?;
+
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        Ok(self.read_exact(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_le_bytes(self.read_exact(4)?.try_into()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(u64::from_le_bytes(self.read_exact(8)?.try_into()?))
+    }
+
+    pub fn read_vint(&mut self) -> Result<u64, Box<dyn Error>> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(format!("File '{0}' has a number that is too long.", self.name).into())
+    }
+
+    /// Reads a number that must fit in a `u32`.
+    pub fn read_vint_u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        let value = self.read_vint()?;
+
+        u32::try_from(value)
+            .map_err(|_| format!("File '{0}' has a number that is too large.", self.name).into())
+    }
+
+    pub fn read_string(&mut self) -> Result<String, Box<dyn Error>> {
+        Ok(String::from_utf8(self.read_bytes()?)?)
+    }
+
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let length = self.read_vint()? as usize;
+
+        Ok(self.read_exact(length)?.to_vec())
+    }
+
+    /// Checks that the whole file has been read.
+    pub fn finish(self) -> Result<(), Box<dyn Error>> {
+        if self.position != self.bytes.len() {
+            return Err(format!("File '{0}' has unexpected trailing bytes.", self.name).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A data directory that holds the files of the last commit.
+#[derive(Clone)]
+pub(crate) struct Directory {
+    path: PathBuf,
+}
+
+impl Directory {
+    /// Opens the directory, creating it if it does not exist yet.
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(path)?;
+
+        Ok(Directory {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.path.join(name).exists()
+    }
+
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        fs::read(self.path.join(name))
+            .map_err(|err| format!("Could not read file '{0}': {1}", name, err).into())
+    }
+
+    /// Reads the `commit` file, if there has been a commit.
+    pub fn read_commit(&self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        if !self.exists(COMMIT_FILE) {
+            return Ok(None);
+        }
+
+        self.read(COMMIT_FILE).map(Some)
+    }
+
+    /// Writes the file under a temporary name, syncs it and renames it, so the file is either
+    /// fully written or not there at all.
+    pub fn write(&self, name: &str, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        let temp_path = self.path.join(format!("{0}.tmp", name));
+
+        let mut file = File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+
+        fs::rename(&temp_path, self.path.join(name))?;
+
+        // Syncing the directory makes the rename itself durable.
+        File::open(&self.path)?.sync_all()?;
+
+        Ok(())
+    }
+
+    /// Opens the file for appending, creating it if it does not exist yet. Unlike `write`, this is
+    /// for files that grow, like the translog, so syncing them is up to the caller.
+    pub fn open_append(&self, name: &str) -> Result<File, Box<dyn Error>> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.join(name))
+            .map_err(|err| format!("Could not open file '{0}': {1}", name, err))?;
+
+        // Syncing the directory makes the creation of the file durable.
+        File::open(&self.path)?.sync_all()?;
+
+        Ok(file)
+    }
+
+    /// Replaces the `commit` file, and then removes the files of segments that are not in
+    /// `files`, since the new commit no longer needs them.
+    pub fn commit(&self, commit: &[u8], files: &[String]) -> Result<(), Box<dyn Error>> {
+        self.write(COMMIT_FILE, commit)?;
+
+        self.remove_unreferenced(files)
+    }
+
+    /// Removes the files of segments that are not in `files`, such as those of a commit that
+    /// failed halfway, so that new segments never find stale files under their name.
+    pub fn remove_unreferenced(&self, files: &[String]) -> Result<(), Box<dyn Error>> {
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if name.starts_with(SEGMENT_FILE_PREFIX) && !files.contains(&name) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The CRC-32 checksum (IEEE) of the bytes, the same one that zip and Lucene use.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffffffff_u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Returns a new, empty directory under the temporary directory. Each call gets its own.
+    pub(crate) fn temp_dir() -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "rucene-test-{0}-{1}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+
+        path
+    }
+
+    #[test]
+    fn can_compute_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn can_write_and_read() {
+        let mut output = DataOutput::new("Test", 1);
+        output.write_u8(7);
+        output.write_u32(70_000);
+        output.write_u64(u64::MAX);
+        output.write_vint(300);
+        output.write_string("café");
+        output.write_bytes(&[0, 255]);
+        let bytes = output.finish();
+
+        let mut input = DataInput::new("test", &bytes, "Test", 1).unwrap();
+
+        assert_eq!(input.read_u8().unwrap(), 7);
+        assert_eq!(input.read_u32().unwrap(), 70_000);
+        assert_eq!(input.read_u64().unwrap(), u64::MAX);
+        assert_eq!(input.read_vint().unwrap(), 300);
+        assert_eq!(input.read_string().unwrap(), "café");
+        assert_eq!(input.read_bytes().unwrap(), vec![0, 255]);
+        assert!(input.read_u8().is_err());
+        assert!(input.finish().is_ok());
+    }
+
+    #[test]
+    fn detects_corrupt_and_incompatible_files() {
+        let mut output = DataOutput::new("Test", 1);
+        output.write_string("back to the future");
+        let bytes = output.finish();
+
+        let error = |bytes: &[u8], codec: &str, version: u32| {
+            DataInput::new("test", bytes, codec, version)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        let mut corrupt = bytes.clone();
+        corrupt[12] ^= 1;
+
+        assert!(error(&corrupt, "Test", 1).starts_with("File 'test' is corrupt"));
+        assert!(error(&bytes[..bytes.len() - 1], "Test", 1).starts_with("File 'test' is corrupt"));
+        assert!(error(&bytes[..2], "Test", 1).contains("truncated"));
+        assert_eq!(
+            error(&bytes, "Other", 1),
+            "File 'test' has codec 'Test', but should have codec 'Other'."
+        );
+        assert_eq!(
+            error(&bytes, "Test", 2),
+            "File 'test' has version 1, but only version 2 is supported."
+        );
+    }
+
+    #[test]
+    fn can_commit() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        assert_eq!(directory.read_commit().unwrap(), None);
+
+        directory.write("_0.terms", &[1]).unwrap();
+        directory.write("_1.terms", &[2]).unwrap();
+        directory.write("notes.txt", &[3]).unwrap();
+        directory.commit(&[4], &["_1.terms".to_string()]).unwrap();
+
+        let directory = Directory::open(&path).unwrap();
+
+        assert_eq!(directory.read_commit().unwrap(), Some(vec![4]));
+        assert_eq!(directory.read("_1.terms").unwrap(), vec![2]);
+
+        // Only files of segments that are not committed are removed.
+        assert!(!directory.exists("_0.terms"));
+        assert!(directory.exists("notes.txt"));
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
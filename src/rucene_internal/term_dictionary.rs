@@ -0,0 +1,207 @@
+//! The term dictionary of a field maps its terms to their ordinals, which address their postings.
+//! While documents are added to a segment, the terms are in a `BTreeMap`. Once the segment is
+//! flushed it no longer changes, and the terms are built into an FST, which is what is written
+//! to disk. With many terms the FST takes a fraction of the memory of the map.
+
+use crate::rucene_internal::automaton::{intersect, Automaton};
+use crate::rucene_internal::fst::Fst;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+#[derive(Clone)]
+pub(crate) enum TermDictionary {
+    // The terms of a segment that documents are added to.
+    Map(BTreeMap<String, u32>),
+
+    // The terms of a segment that no longer changes.
+    Fst(Fst),
+}
+
+impl Default for TermDictionary {
+    fn default() -> Self {
+        TermDictionary::Map(BTreeMap::new())
+    }
+}
+
+impl TermDictionary {
+    pub fn get(&self, term: &str) -> Option<u32> {
+        match self {
+            TermDictionary::Map(map) => map.get(term).copied(),
+            TermDictionary::Fst(fst) => fst.get(term).map(|ordinal| ordinal as u32),
+        }
+    }
+
+    /// Adds a term that is not in the dictionary yet. A dictionary that is an FST turns back into
+    /// a map first, since FSTs do not change.
+    pub fn insert(&mut self, term: String, ordinal: u32) {
+        if let TermDictionary::Fst(_) = self {
+            *self = TermDictionary::Map(self.iter().collect());
+        }
+
+        if let TermDictionary::Map(map) = self {
+            map.insert(term, ordinal);
+        }
+    }
+
+    /// Builds the FST of the terms, once no more terms are added.
+    pub fn finish(&mut self) {
+        if let TermDictionary::Map(_) = self {
+            *self = TermDictionary::Fst(self.fst().into_owned());
+        }
+    }
+
+    /// Returns the FST of the terms, which is built if the terms are still in a map.
+    pub fn fst(&self) -> Cow<'_, Fst> {
+        match self {
+            TermDictionary::Map(map) => Cow::Owned(Fst::from_sorted(
+                map.iter()
+                    .map(|(term, ordinal)| (term.as_str(), *ordinal as u64)),
+            )),
+            TermDictionary::Fst(fst) => Cow::Borrowed(fst),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        match self {
+            TermDictionary::Map(map) => map.len(),
+            TermDictionary::Fst(fst) => fst.len(),
+        }
+    }
+
+    /// An estimate of the bytes that the terms take.
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            TermDictionary::Map(map) => map.keys().map(|term| term.len() + 4).sum(),
+            TermDictionary::Fst(fst) => fst.size_in_bytes(),
+        }
+    }
+
+    /// Returns the terms and their ordinals, in ascending order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (String, u32)> + '_> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Returns the terms within the bounds and their ordinals, in ascending order.
+    pub fn range<'a>(
+        &'a self,
+        lower: Bound<&'a str>,
+        upper: Bound<&'a str>,
+    ) -> Box<dyn Iterator<Item = (String, u32)> + 'a> {
+        match self {
+            TermDictionary::Map(map) => {
+                // `BTreeMap::range` panics on inverted bounds, while for us they just contain no
+                // terms.
+                let is_empty = match (lower, upper) {
+                    (Bound::Included(lower), Bound::Included(upper)) => lower > upper,
+                    (Bound::Included(lower), Bound::Excluded(upper))
+                    | (Bound::Excluded(lower), Bound::Included(upper))
+                    | (Bound::Excluded(lower), Bound::Excluded(upper)) => lower >= upper,
+                    _ => false,
+                };
+
+                if is_empty {
+                    return Box::new(std::iter::empty());
+                }
+
+                Box::new(
+                    map.range::<str, _>((lower, upper))
+                        .map(|(term, ordinal)| (term.clone(), *ordinal)),
+                )
+            }
+            TermDictionary::Fst(fst) => Box::new(
+                fst.range(lower, upper)
+                    .map(|(term, ordinal)| (term, ordinal as u32)),
+            ),
+        }
+    }
+
+    /// Returns the terms that start with the prefix and whose remainder is accepted by the
+    /// automaton, in ascending order.
+    pub fn intersect<A: Automaton>(&self, prefix: &str, automaton: &A) -> Vec<(String, A::State)> {
+        match self {
+            TermDictionary::Map(map) => intersect(map, prefix, automaton)
+                .into_iter()
+                .map(|(term, state)| (term.to_string(), state))
+                .collect(),
+            TermDictionary::Fst(fst) => fst.intersect(prefix, automaton),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rucene_internal::automaton::LevenshteinAutomaton;
+
+    /// Words of one to four syllables, some with a number, like the terms of a large corpus.
+    fn vocabulary(size: usize) -> BTreeMap<String, u32> {
+        const SYLLABLES: [&str; 24] = [
+            "an", "ba", "con", "de", "er", "fu", "ga", "in", "ka", "lo", "man", "ne", "or", "pro",
+            "qui", "re", "sta", "ter", "un", "ver", "wa", "xi", "yo", "zen",
+        ];
+
+        let mut seed: u64 = 42;
+        let mut next = move |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        let mut terms = BTreeMap::new();
+
+        while terms.len() < size {
+            let mut term = String::new();
+
+            for _ in 0..=next(4) {
+                term.push_str(SYLLABLES[next(24) as usize]);
+            }
+            if next(4) == 0 {
+                term.push_str(&next(1000).to_string());
+            }
+
+            let ordinal = terms.len() as u32;
+            terms.entry(term).or_insert(ordinal);
+        }
+
+        terms
+    }
+
+    #[test]
+    fn fst_has_terms_of_map() {
+        let map = vocabulary(1000);
+        let mut dictionary = TermDictionary::Map(map.clone());
+        dictionary.finish();
+
+        assert_eq!(dictionary.len(), map.len());
+        assert!(dictionary.iter().eq(map.clone()));
+
+        for (term, ordinal) in &map {
+            assert_eq!(dictionary.get(term), Some(*ordinal));
+        }
+
+        let automaton = LevenshteinAutomaton::new("ban", 1, false);
+        let map_dictionary = TermDictionary::Map(map);
+
+        assert_eq!(
+            dictionary.intersect("", &automaton).len(),
+            map_dictionary.intersect("", &automaton).len()
+        );
+        assert!(dictionary
+            .range(Bound::Included("de"), Bound::Excluded("fu"))
+            .eq(map_dictionary.range(Bound::Included("de"), Bound::Excluded("fu"))));
+    }
+
+    #[test]
+    fn can_insert_into_fst() {
+        let mut dictionary = TermDictionary::default();
+        dictionary.insert("future".to_string(), 0);
+        dictionary.finish();
+        dictionary.insert("back".to_string(), 1);
+
+        assert_eq!(dictionary.get("back"), Some(1));
+        assert_eq!(dictionary.get("future"), Some(0));
+    }
+}
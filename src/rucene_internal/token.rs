@@ -1,16 +1,31 @@
 pub type Tokens = Vec<Token>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Token {
     pub value: String,
+
+    // Where the token was found in the analysed text, before character filtering, if the
+    // tokenizer keeps track of it.
+    pub offsets: Option<Offsets>,
 }
 
 impl Token {
     pub fn new(value: String) -> Self {
-        Token { value }
+        Token {
+            value,
+            offsets: None,
+        }
+    }
+
+    pub fn with_offsets(value: String, start: usize, end: usize) -> Self {
+        Token {
+            value,
+            offsets: Some(Offsets { start, end }),
+        }
     }
 }
 
+/// Tokens are compared by their value only, wherever they were found.
 impl PartialEq<Self> for Token {
     fn eq(&self, other: &Self) -> bool {
         self.value == other.value
@@ -18,3 +33,11 @@ impl PartialEq<Self> for Token {
 }
 
 impl Eq for Token {}
+
+/// Byte offsets of a token in the text it was analysed from. Tokenizers return offsets into the
+/// text after character filtering, which the `Analyser` corrects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offsets {
+    pub start: usize,
+    pub end: usize,
+}
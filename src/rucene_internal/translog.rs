@@ -0,0 +1,765 @@
+//! The translog, a write-ahead log of the changes to an index, like Elasticsearch's. Every
+//! document that is indexed and every delete is appended to it before it is applied, and numbered
+//! with a sequence number. Commits record the sequence number of the last operation they contain,
+//! so opening an index replays only the operations that came after, and a commit empties the
+//! translog.
+//!
+//! Each operation is a record of its own: its length, followed by a file in the format of
+//! `store`, with a header and a checksum. Every sync writes a checkpoint of how far the translog
+//! is durable, like Elasticsearch's `translog.ckp`. A crash while appending leaves the records
+//! after the checkpoint incomplete at worst, so when the translog is opened, they are dropped from
+//! the first record that is cut off or corrupt. Before the checkpoint, that is an error.
+//! # Example
+//! ```
+//! use rucene::rucene_internal::translog::Durability;
+//! use rucene::IndexWriter;
+//! use std::time::Duration;
+//!
+//! let writer = IndexWriter::new().with_durability(Durability::Interval(Duration::from_secs(5)));
+//! ```
+
+use crate::rucene_internal::document::{AnalysedDocument, StoredFields};
+use crate::rucene_internal::store::{DataInput, DataOutput, Directory};
+use crate::rucene_internal::token::{Token, Tokens};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub(crate) const TRANSLOG_FILE: &str = "translog";
+
+// Records up to where the translog was last synced, like Elasticsearch's `translog.ckp`.
+const CHECKPOINT_FILE: &str = "translog.ckp";
+
+const TRANSLOG_CODEC: &str = "RuceneTranslog";
+const CHECKPOINT_CODEC: &str = "RuceneTranslogCheckpoint";
+const VERSION: u32 = 1;
+
+// Shorter sync intervals are raised to this, like Elasticsearch's `index.translog.sync_interval`,
+// so that the translog is not synced in a tight loop.
+pub const MIN_SYNC_INTERVAL: Duration = Duration::from_millis(100);
+
+const INDEX_OPERATION: u8 = 0;
+const DELETE_OPERATION: u8 = 1;
+
+/// When the translog is synced to disk. Operations reach the operating system right away, so they
+/// survive a crash of the process either way; syncing makes them survive a power loss too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// Every operation is synced before it returns. This is the default, and the safest.
+    #[default]
+    Request,
+    /// A background thread syncs at this interval, so a power loss loses at most the operations
+    /// of the last interval. Intervals shorter than `MIN_SYNC_INTERVAL` are raised to it.
+    Interval(Duration),
+    /// The translog is only synced when the index is committed, and otherwise left to the
+    /// operating system.
+    Async,
+}
+
+/// A change to the index.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Operation {
+    Index(AnalysedDocument),
+    Delete(String),
+}
+
+pub(crate) struct Translog {
+    // Shared with the thread that syncs it at an interval, which stops once the translog is gone.
+    file: Arc<Mutex<TranslogFile>>,
+
+    durability: Durability,
+
+    // Syncs the file when the durability has an interval.
+    sync_thread: Option<SyncThread>,
+
+    // The sequence number of the last operation.
+    seq_no: u64,
+
+    // Set when an operation could not be written nor removed again, after which the translog
+    // may not end in a complete record, so it takes no more operations.
+    failed: bool,
+
+    #[cfg(test)]
+    fault: Option<Fault>,
+}
+
+/// A failure to inject into the next append, to test that the translog recovers from it.
+#[cfg(test)]
+#[derive(Clone, Copy)]
+enum Fault {
+    // Only part of the record is written, like when the disk is full.
+    PartialWrite,
+    // The record is written but not synced.
+    Sync,
+}
+
+/// The translog file, and the directory that its checkpoint is written to.
+struct TranslogFile {
+    file: File,
+    directory: Directory,
+
+    // Where the last complete record ends.
+    size_in_bytes: u64,
+}
+
+impl TranslogFile {
+    /// Syncs the file, and then records in the checkpoint that it is durable up to its size.
+    fn sync(&mut self) -> Result<(), Box<dyn Error>> {
+        self.file.sync_data()?;
+        write_checkpoint(&self.directory, self.size_in_bytes)
+    }
+}
+
+impl Translog {
+    /// Opens the translog of the directory, and returns the operations that came after
+    /// `committed_seq_no`, the last operation of the commit. The records up to the checkpoint have
+    /// been synced, so they have to be intact. After it, the records that did not finish before a
+    /// crash are removed, so new operations are appended after the last complete one.
+    pub fn open(
+        directory: &Directory,
+        committed_seq_no: u64,
+    ) -> Result<(Self, Vec<Operation>), Box<dyn Error>> {
+        let bytes = if directory.exists(TRANSLOG_FILE) {
+            directory.read(TRANSLOG_FILE)?
+        } else {
+            vec![]
+        };
+
+        let checkpoint = read_checkpoint(directory)? as usize;
+
+        if bytes.len() < checkpoint {
+            return Err(format!(
+                "File '{0}' has {1} bytes, but its checkpoint says {2} bytes were synced.",
+                TRANSLOG_FILE,
+                bytes.len(),
+                checkpoint
+            )
+            .into());
+        }
+
+        let mut seq_no = committed_seq_no;
+        let mut operations = vec![];
+        let mut position = 0;
+
+        while let Some(end) = record_end(&bytes, position, checkpoint)? {
+            let (record_seq_no, operation) = read_operation(&bytes[position + 4..end])?;
+
+            if record_seq_no > committed_seq_no {
+                operations.push(operation);
+            }
+
+            seq_no = seq_no.max(record_seq_no);
+            position = end;
+        }
+
+        let file = directory.open_append(TRANSLOG_FILE)?;
+
+        if position < bytes.len() {
+            file.set_len(position as u64)?;
+        }
+
+        let mut file = TranslogFile {
+            file,
+            directory: directory.clone(),
+            size_in_bytes: position as u64,
+        };
+
+        if position != checkpoint {
+            file.sync()?;
+        }
+
+        let translog = Translog {
+            file: Arc::new(Mutex::new(file)),
+            durability: Durability::default(),
+            sync_thread: None,
+            seq_no,
+            failed: false,
+            #[cfg(test)]
+            fault: None,
+        };
+
+        Ok((translog, operations))
+    }
+
+    /// Syncs the translog as the durability says, starting a thread that syncs it if there is an
+    /// interval. The thread of the previous durability, if any, is stopped.
+    pub fn set_durability(&mut self, durability: Durability) {
+        if let Some(sync_thread) = self.sync_thread.take() {
+            sync_thread.stop();
+        }
+
+        self.durability = match durability {
+            Durability::Interval(interval) => {
+                let interval = interval.max(MIN_SYNC_INTERVAL);
+                self.sync_thread = Some(SyncThread::start(Arc::downgrade(&self.file), interval));
+
+                Durability::Interval(interval)
+            }
+            durability => durability,
+        };
+    }
+
+    pub fn seq_no(&self) -> u64 {
+        self.seq_no
+    }
+
+    pub fn size_in_bytes(&self) -> u64 {
+        self.file.lock().unwrap().size_in_bytes
+    }
+
+    /// Appends the operation with the next sequence number. If that fails, whatever part of it
+    /// was written is removed again, so that the operation is not replayed, and the next one
+    /// gets the same sequence number and follows the last complete record.
+    pub fn add(&mut self, operation: &Operation) -> Result<(), Box<dyn Error>> {
+        if self.failed {
+            return Err(format!(
+                "File '{0}' failed to remove an operation that could not be written, and takes \
+                 no more operations until the index is opened again.",
+                TRANSLOG_FILE
+            )
+            .into());
+        }
+
+        let record = write_operation(self.seq_no + 1, operation);
+
+        let mut bytes = Vec::with_capacity(record.len() + 4);
+        bytes.extend((record.len() as u32).to_le_bytes());
+        bytes.extend(record);
+
+        let mut file = self.file.lock().unwrap();
+        let size_in_bytes = file.size_in_bytes;
+
+        if let Err(err) = self.append(&mut file, &bytes) {
+            // Syncing the shortened file makes all of it durable, so the checkpoint can be its
+            // size, whether or not the failed append had written a checkpoint already.
+            file.size_in_bytes = size_in_bytes;
+
+            if file
+                .file
+                .set_len(size_in_bytes)
+                .map_err(Box::<dyn Error>::from)
+                .and_then(|_| file.sync())
+                .is_err()
+            {
+                self.failed = true;
+            }
+
+            return Err(err);
+        }
+
+        self.seq_no += 1;
+
+        Ok(())
+    }
+
+    fn append(&self, file: &mut TranslogFile, bytes: &[u8]) -> Result<(), Box<dyn Error>> {
+        #[cfg(test)]
+        match self.fault {
+            Some(Fault::PartialWrite) => {
+                file.file.write_all(&bytes[..bytes.len() / 2])?;
+                return Err("No space left on device".into());
+            }
+            Some(Fault::Sync) => {
+                file.file.write_all(bytes)?;
+                return Err("Input/output error".into());
+            }
+            None => {}
+        }
+
+        file.file.write_all(bytes)?;
+        file.size_in_bytes += bytes.len() as u64;
+
+        if self.durability == Durability::Request {
+            file.sync()?;
+        }
+
+        Ok(())
+    }
+
+    /// Empties the translog, once a commit contains all of its operations. Sequence numbers go on
+    /// from the last operation.
+    pub fn trim(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut file = self.file.lock().unwrap();
+
+        // The checkpoint goes first: after a crash in between, the records it no longer covers
+        // are all in the commit, and are not replayed.
+        file.size_in_bytes = 0;
+        write_checkpoint(&file.directory, 0)?;
+
+        file.file.set_len(0)?;
+        file.file.sync_all()?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Translog {
+    fn drop(&mut self) {
+        if let Some(sync_thread) = self.sync_thread.take() {
+            sync_thread.stop();
+        }
+    }
+}
+
+/// A thread that syncs the translog at an interval, until it is stopped.
+struct SyncThread {
+    // Dropping it wakes the thread up, which then stops.
+    stop: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl SyncThread {
+    fn start(file: Weak<Mutex<TranslogFile>>, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let handle = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let Some(file) = file.upgrade() else {
+                    return;
+                };
+
+                // A failed sync is tried again at the next interval; the next commit syncs as
+                // well.
+                let _ = file.lock().unwrap().sync();
+            }
+        });
+
+        SyncThread { stop, handle }
+    }
+
+    /// Stops the thread, and waits until it has, so that it no longer syncs.
+    fn stop(self) {
+        drop(self.stop);
+        let _ = self.handle.join();
+    }
+}
+
+/// Returns the offset up to which the translog was synced, or 0 if it never was.
+fn read_checkpoint(directory: &Directory) -> Result<u64, Box<dyn Error>> {
+    if !directory.exists(CHECKPOINT_FILE) {
+        return Ok(0);
+    }
+
+    let bytes = directory.read(CHECKPOINT_FILE)?;
+    let mut input = DataInput::new(CHECKPOINT_FILE, &bytes, CHECKPOINT_CODEC, VERSION)?;
+    let offset = input.read_vint()?;
+    input.finish()?;
+
+    Ok(offset)
+}
+
+fn write_checkpoint(directory: &Directory, offset: u64) -> Result<(), Box<dyn Error>> {
+    let mut output = DataOutput::new(CHECKPOINT_CODEC, VERSION);
+    output.write_vint(offset);
+
+    directory.write(CHECKPOINT_FILE, &output.finish())
+}
+
+/// Returns where the record at the position ends, unless the translog ends there. Records before
+/// the checkpoint have been synced, so one that is cut off or corrupt is an error. After it, such
+/// a record is the tail of an append that did not finish, and ends the translog.
+fn record_end(
+    bytes: &[u8],
+    position: usize,
+    checkpoint: usize,
+) -> Result<Option<usize>, Box<dyn Error>> {
+    if position == bytes.len() {
+        return Ok(None);
+    }
+
+    let limit = if position < checkpoint {
+        checkpoint
+    } else {
+        bytes.len()
+    };
+
+    let end = match bytes.get(position..position + 4) {
+        Some(length) => position + 4 + u32::from_le_bytes(length.try_into()?) as usize,
+        None => usize::MAX,
+    };
+
+    let error = if end > limit {
+        Some(
+            format!(
+                "File '{0}' is corrupt: the record at {1} ends past the synced bytes.",
+                TRANSLOG_FILE, position
+            )
+            .into(),
+        )
+    } else {
+        DataInput::new(
+            TRANSLOG_FILE,
+            &bytes[position + 4..end],
+            TRANSLOG_CODEC,
+            VERSION,
+        )
+        .err()
+    };
+
+    match error {
+        None => Ok(Some(end)),
+        Some(_) if position >= checkpoint => Ok(None),
+        Some(err) => Err(err),
+    }
+}
+
+fn write_operation(seq_no: u64, operation: &Operation) -> Vec<u8> {
+    let mut output = DataOutput::new(TRANSLOG_CODEC, VERSION);
+    output.write_vint(seq_no);
+
+    match operation {
+        Operation::Index(document) => {
+            output.write_u8(INDEX_OPERATION);
+            output.write_string(&document.id);
+
+            output.write_vint(document.fields.len() as u64);
+            for (field, tokens) in &document.fields {
+                output.write_string(field);
+
+                output.write_vint(tokens.len() as u64);
+                for token in tokens {
+                    output.write_string(&token.value);
+
+                    match token.offsets {
+                        Some(offsets) => {
+                            output.write_u8(1);
+                            output.write_vint(offsets.start as u64);
+                            output.write_vint(offsets.end as u64);
+                        }
+                        None => output.write_u8(0),
+                    }
+                }
+            }
+
+            output.write_vint(document.stored_fields.len() as u64);
+            for (field, text) in &document.stored_fields {
+                output.write_string(field);
+                output.write_string(text);
+            }
+        }
+        Operation::Delete(id) => {
+            output.write_u8(DELETE_OPERATION);
+            output.write_string(id);
+        }
+    }
+
+    output.finish()
+}
+
+fn read_operation(record: &[u8]) -> Result<(u64, Operation), Box<dyn Error>> {
+    let mut input = DataInput::new(TRANSLOG_FILE, record, TRANSLOG_CODEC, VERSION)?;
+    let seq_no = input.read_vint()?;
+
+    let operation = match input.read_u8()? {
+        INDEX_OPERATION => {
+            let id = input.read_string()?;
+
+            let mut fields = BTreeMap::new();
+            for _ in 0..input.read_vint()? {
+                let field = input.read_string()?;
+
+                let mut tokens = Tokens::new();
+                for _ in 0..input.read_vint()? {
+                    let value = input.read_string()?;
+
+                    tokens.push(match input.read_u8()? {
+                        0 => Token::new(value),
+                        _ => {
+                            let start = input.read_vint()? as usize;
+                            let end = input.read_vint()? as usize;
+                            Token::with_offsets(value, start, end)
+                        }
+                    });
+                }
+
+                fields.insert(field, tokens);
+            }
+
+            let mut stored_fields = StoredFields::new();
+            for _ in 0..input.read_vint()? {
+                let field = input.read_string()?;
+                stored_fields.insert(field, input.read_string()?);
+            }
+
+            Operation::Index(
+                AnalysedDocument::from_fields(id, fields).with_stored_fields(stored_fields),
+            )
+        }
+        DELETE_OPERATION => Operation::Delete(input.read_string()?),
+        other => {
+            return Err(format!(
+                "File '{0}' has an unknown operation {1}.",
+                TRANSLOG_FILE, other
+            )
+            .into())
+        }
+    };
+
+    input.finish()?;
+
+    Ok((seq_no, operation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rucene_internal::store::tests::temp_dir;
+    use std::fs;
+
+    fn document(id: &str) -> AnalysedDocument {
+        let tokens = vec![
+            Token::with_offsets("back".to_string(), 0, 4),
+            Token::new("future".to_string()),
+        ];
+
+        AnalysedDocument::new(id.to_string(), tokens)
+            .with_stored_fields(StoredFields::from([("body".to_string(), id.to_string())]))
+    }
+
+    fn operations() -> Vec<Operation> {
+        vec![
+            Operation::Index(document("0")),
+            Operation::Index(document("1")),
+            Operation::Delete("0".to_string()),
+        ]
+    }
+
+    #[test]
+    fn can_add_and_replay() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, replayed) = Translog::open(&directory, 0).unwrap();
+        assert!(replayed.is_empty());
+
+        for operation in &operations() {
+            translog.add(operation).unwrap();
+        }
+        assert_eq!(translog.seq_no(), 3);
+        drop(translog);
+
+        let (translog, replayed) = Translog::open(&directory, 0).unwrap();
+        assert_eq!(replayed, operations());
+        assert_eq!(translog.seq_no(), 3);
+
+        // Operations that a commit contains are not replayed.
+        let (_, replayed) = Translog::open(&directory, 2).unwrap();
+        assert_eq!(replayed, vec![Operation::Delete("0".to_string())]);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn drops_truncated_tail() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        for operation in &operations()[..2] {
+            translog.add(operation).unwrap();
+        }
+
+        // The last record is not synced, so it is after the checkpoint.
+        translog.set_durability(Durability::Async);
+        translog.add(&operations()[2]).unwrap();
+        let size = translog.size_in_bytes() as usize;
+        drop(translog);
+
+        let bytes = fs::read(path.join(TRANSLOG_FILE)).unwrap();
+        let checkpoint = fs::read(path.join(CHECKPOINT_FILE)).unwrap();
+        let last_record = size - write_operation(3, &operations()[2]).len() - 4;
+
+        // A crash may cut the last record off anywhere, even within its length.
+        for cut in [1, 3, 4, 5, size - last_record - 1] {
+            fs::write(path.join(TRANSLOG_FILE), &bytes[..size - cut]).unwrap();
+            fs::write(path.join(CHECKPOINT_FILE), &checkpoint).unwrap();
+
+            let (mut translog, replayed) = Translog::open(&directory, 0).unwrap();
+            assert_eq!(replayed.len(), 2);
+            assert_eq!(translog.seq_no(), 2);
+            assert_eq!(translog.size_in_bytes() as usize, last_record);
+
+            // New operations follow the last complete one.
+            translog.add(&Operation::Delete("1".to_string())).unwrap();
+            drop(translog);
+
+            let (_, replayed) = Translog::open(&directory, 0).unwrap();
+            assert_eq!(replayed[2], Operation::Delete("1".to_string()));
+        }
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fails_on_corrupt_record_before_tail() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        for operation in &operations() {
+            translog.add(operation).unwrap();
+        }
+        drop(translog);
+
+        let mut bytes = fs::read(path.join(TRANSLOG_FILE)).unwrap();
+        bytes[10] ^= 0xff;
+        fs::write(path.join(TRANSLOG_FILE), bytes).unwrap();
+
+        let error = Translog::open(&directory, 0).err().unwrap().to_string();
+
+        assert!(error.starts_with("File 'translog' is corrupt"));
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fails_on_corrupt_length_before_checkpoint() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        for operation in &operations() {
+            translog.add(operation).unwrap();
+        }
+        drop(translog);
+
+        // A length past the end would look like a cut off tail, but the records are synced.
+        let mut bytes = fs::read(path.join(TRANSLOG_FILE)).unwrap();
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(path.join(TRANSLOG_FILE), &bytes).unwrap();
+
+        let error = Translog::open(&directory, 0).err().unwrap().to_string();
+
+        assert_eq!(
+            error,
+            "File 'translog' is corrupt: the record at 0 ends past the synced bytes."
+        );
+        assert_eq!(fs::read(path.join(TRANSLOG_FILE)).unwrap(), bytes);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn removes_partially_written_operation() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        translog.add(&operations()[0]).unwrap();
+        let size = translog.size_in_bytes();
+
+        translog.fault = Some(Fault::PartialWrite);
+        assert!(translog.add(&operations()[1]).is_err());
+        assert_eq!(translog.seq_no(), 1);
+        assert_eq!(fs::metadata(path.join(TRANSLOG_FILE)).unwrap().len(), size);
+
+        translog.fault = None;
+        translog.add(&operations()[2]).unwrap();
+        drop(translog);
+
+        // The failed operation left nothing in the middle of the translog.
+        let (translog, replayed) = Translog::open(&directory, 0).unwrap();
+        assert_eq!(
+            replayed,
+            vec![
+                Operation::Index(document("0")),
+                Operation::Delete("0".to_string())
+            ]
+        );
+        assert_eq!(translog.seq_no(), 2);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn removes_operation_that_failed_to_sync() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        translog.add(&operations()[0]).unwrap();
+
+        translog.fault = Some(Fault::Sync);
+        assert!(translog.add(&operations()[1]).is_err());
+        assert_eq!(translog.seq_no(), 1);
+
+        translog.fault = None;
+        translog.add(&operations()[2]).unwrap();
+        assert_eq!(translog.seq_no(), 2);
+        drop(translog);
+
+        // The failed operation is not replayed, and its sequence number is not used twice.
+        let (_, replayed) = Translog::open(&directory, 0).unwrap();
+        assert_eq!(
+            replayed,
+            vec![
+                Operation::Index(document("0")),
+                Operation::Delete("0".to_string())
+            ]
+        );
+
+        let (_, replayed) = Translog::open(&directory, 1).unwrap();
+        assert_eq!(replayed, vec![Operation::Delete("0".to_string())]);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn can_trim() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        translog.set_durability(Durability::Interval(MIN_SYNC_INTERVAL));
+        for operation in &operations() {
+            translog.add(operation).unwrap();
+        }
+        translog.trim().unwrap();
+        translog.add(&Operation::Delete("1".to_string())).unwrap();
+        drop(translog);
+
+        // Sequence numbers go on after a trim.
+        let (translog, replayed) = Translog::open(&directory, 3).unwrap();
+        assert_eq!(replayed, vec![Operation::Delete("1".to_string())]);
+        assert_eq!(translog.seq_no(), 4);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn raises_short_sync_intervals() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        translog.set_durability(Durability::Interval(Duration::ZERO));
+
+        assert_eq!(translog.durability, Durability::Interval(MIN_SYNC_INTERVAL));
+
+        drop(translog);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn stops_previous_sync_thread() {
+        let path = temp_dir();
+        let directory = Directory::open(&path).unwrap();
+
+        let (mut translog, _) = Translog::open(&directory, 0).unwrap();
+        translog.set_durability(Durability::Interval(MIN_SYNC_INTERVAL));
+        translog.set_durability(Durability::Async);
+        translog.add(&operations()[0]).unwrap();
+
+        // Nothing syncs the operation anymore.
+        thread::sleep(MIN_SYNC_INTERVAL * 3);
+        assert_eq!(read_checkpoint(&directory).unwrap(), 0);
+
+        drop(translog);
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+//! Measures the memory of a term dictionary as an FST and as a map. The measurement counts
+//! allocations with a global allocator, so it is a test binary of its own rather than a unit
+//! test, whose allocator would count the allocations of every other test too.
+
+use rucene::rucene_internal::fst::Fst;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Counts the bytes that each thread has allocated and not freed yet.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ =
+            ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size() as isize));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ =
+            ALLOCATED.try_with(|allocated| allocated.set(allocated.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns what `build` returns, and the bytes it keeps allocated.
+fn measure<T>(build: impl FnOnce() -> T) -> (T, isize) {
+    let before = ALLOCATED.with(Cell::get);
+    let built = build();
+
+    (built, ALLOCATED.with(Cell::get) - before)
+}
+
+/// Words of one to four syllables, some with a number, like the terms of a large corpus.
+fn vocabulary(size: usize) -> Vec<String> {
+    const SYLLABLES: [&str; 24] = [
+        "an", "ba", "con", "de", "er", "fu", "ga", "in", "ka", "lo", "man", "ne", "or", "pro",
+        "qui", "re", "sta", "ter", "un", "ver", "wa", "xi", "yo", "zen",
+    ];
+
+    let mut seed: u64 = 42;
+    let mut next = move |bound: u64| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) % bound
+    };
+
+    let mut terms = BTreeMap::new();
+
+    while terms.len() < size {
+        let mut term = String::new();
+
+        for _ in 0..=next(4) {
+            term.push_str(SYLLABLES[next(24) as usize]);
+        }
+        if next(4) == 0 {
+            term.push_str(&next(1000).to_string());
+        }
+
+        terms.insert(term, ());
+    }
+
+    terms.into_keys().collect()
+}
+
+#[test]
+fn fst_takes_less_memory_than_map() {
+    // Finished segments number their terms in order.
+    let terms = vocabulary(100_000);
+
+    let (map, map_bytes) = measure(|| {
+        terms
+            .iter()
+            .enumerate()
+            .map(|(ordinal, term)| (term.clone(), ordinal as u32))
+            .collect::<BTreeMap<String, u32>>()
+    });
+    let (fst, fst_bytes) = measure(|| {
+        Fst::from_sorted(
+            terms
+                .iter()
+                .enumerate()
+                .map(|(ordinal, term)| (term.as_str(), ordinal as u64)),
+        )
+    });
+
+    assert_eq!(map.len(), fst.len());
+    assert!(fst_bytes * 5 < map_bytes);
+}